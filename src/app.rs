@@ -1,6 +1,6 @@
 use std::{fmt::{self, Display}, path::PathBuf};
 use std::time::{Duration, Instant};
-use chrono::Local;
+use chrono::{Datelike, Local, NaiveDate};
 
 use adw::prelude::*;
 use billing_view::BillingInit;
@@ -11,23 +11,42 @@ mod author_view;
 mod product;
 mod client;
 mod address;
+mod service_date;
+mod attachment;
+mod rights_transfer;
 mod editable_combo_row;
+mod date_row;
+mod client_export;
 mod client_form;
+mod client_import;
 mod client_view;
 mod client_selector_group;
 mod products_view;
 mod billing_view;
+mod ledger_view;
+mod dashboard_view;
+mod declaration_view;
 mod pdf_viewer;
 
-pub use author_view::{Author, AuthorFormInput, AuthorFormOutput, AuthorFormModel};
-pub use product::Product;
+pub use author_view::{Author, LegalTerms, AuthorFormInput, AuthorFormOutput, AuthorFormModel};
+pub use product::{Product, Unit};
 pub use address::Address;
-pub use client::{Client, ClientName};
+pub use service_date::ServiceDate;
+pub use attachment::{Attachment, AttachmentKind};
+pub use rights_transfer::{RemunerationSplit, RightsTransfer};
+pub use client::{Client, ClientDefaults, ClientId, ClientName, DeliveryAddress};
 use client_form::{ClientFormModel, ClientFormOutput};
-use client_view::{ClientViewModel, ClientViewOutput};
-use products_view::ProductsModel;
-pub use billing_view::{BillType, BillingModel, BillingOutput, BillingInput};
+use client_view::{ClientViewInput, ClientViewModel, ClientViewOutput};
+use products_view::{ProductsInput, ProductsModel};
+use ledger_view::{LedgerInput, LedgerModel};
+use dashboard_view::{DashboardInput, DashboardModel};
+use declaration_view::{DeclarationInput, DeclarationModel};
+pub use billing_view::{BillType, BillingModel, BillingOutput, BillingInput, PaymentTerms};
 use crate::{latex::{InvoiceData, PdfFile, Template}, APP_NAME};
+use crate::archive::Archive;
+use crate::files;
+use crate::contributions::{DiffuseurContributions, Withholding};
+use crate::money::Money;
 use pdf_viewer::{PdfViewerModel, PdfViewerMsg};
 use crate::CFG;
 
@@ -42,13 +61,28 @@ pub(crate) enum AppMsg {
     PickSignature,
     BillTypeChanged(BillType),
     BillNumberChanged(String),
+    QuoteChanged(Option<String>),
     BillNature(String),
+    BillDate(NaiveDate),
+    PaymentTermsChanged(PaymentTerms),
+    DevisValidity(u32),
+    ServiceDateChanged(Option<ServiceDate>),
     Diffuseur(bool),
+    Dispensed(bool),
     DispenseSelected(Option<PathBuf>),
     PickDispense,
     ClientListEdited(Vec<Client>),
     ClientEdited(Client),
+    ClientDefaults(ClientDefaults),
     ProductsEdited(Vec<Product>),
+    PickAttachment,
+    AttachmentSelected(Option<PathBuf>),
+    AttachmentsEdited(Vec<Attachment>),
+    /// show an attachment in the pdf viewer instead of the invoice
+    PreviewAttachment(String),
+    RightsTransferEdited(Option<RightsTransfer>),
+    /// payments of archived invoices were recorded
+    PaymentsEdited,
 
     OpenPdf,
     ShowPdf,
@@ -59,6 +93,7 @@ pub(crate) enum AppMsg {
 
     ResetShowSignatureDialog,
     ResetShowDispenseDialog,
+    ResetShowAttachmentDialog,
     /// does nothing
     Null,
 }
@@ -81,22 +116,36 @@ pub(crate) struct AppModel {
     client_view: Controller<ClientViewModel>,
     products_view: Controller<ProductsModel>,
     billing_view: Controller<BillingModel>,
+    ledger_view: Controller<LedgerModel>,
+    dashboard_view: Controller<DashboardModel>,
+    declaration_view: Controller<DeclarationModel>,
     pdf_viewer: Controller<PdfViewerModel>,
     is_form_valid: bool,
     status: UpToDate,
     show_signature_dialog: bool,
     show_dispense_dialog: bool,
+    show_attachment_dialog: bool,
     show_pdf: bool,
     pdf: Option<PdfFile>,
 
     pub(crate) author: Author,
     pub(crate) bill_type: BillType,
     pub(crate) number: String,
+    /// number of the devis accepted by the facture
+    pub(crate) quote: Option<String>,
+    pub(crate) date: NaiveDate,
+    pub(crate) payment_terms: PaymentTerms,
+    /// devis validity in days
+    pub(crate) devis_validity: u32,
+    pub(crate) service_date: Option<ServiceDate>,
     pub(crate) client: Client,
     pub(crate) nature: String,
     pub(crate) diffuseur: bool,
-    pub(crate) dispense: Option<PathBuf>,
+    /// false when contributions are withheld by the diffuseur (précompte)
+    pub(crate) dispensed: bool,
     pub(crate) products: Vec<Product>,
+    pub(crate) attachments: Vec<Attachment>,
+    pub(crate) rights_transfer: Option<RightsTransfer>,
 }
 
 impl Display for AppModel {
@@ -161,6 +210,9 @@ impl SimpleComponent for AppModel {
                             add_titled_with_icon[Some("bill"), "Facture", "document-edit-symbolic"] = model.billing_view.widget(),
                             add_titled_with_icon[Some("client"), "Client", "user-info-symbolic"] = model.client_view.widget(),
                             add_titled_with_icon[Some("products"), "Produits", "view-list-bullet-symbolic"] = model.products_view.widget(),
+                            add_titled_with_icon[Some("ledger"), "Recettes", "x-office-spreadsheet-symbolic"] = model.ledger_view.widget(),
+                            add_titled_with_icon[Some("dashboard"), "Activité", "utilities-system-monitor-symbolic"] = model.dashboard_view.widget(),
+                            add_titled_with_icon[Some("declaration"), "Déclaration", "document-send-symbolic"] = model.declaration_view.widget(),
                         },
 
 
@@ -243,6 +295,37 @@ impl SimpleComponent for AppModel {
             );
         }
 
+        if model.show_attachment_dialog {
+            sender.input(AppMsg::ResetShowAttachmentDialog);
+            let filter = gtk::FileFilter::new();
+            filter.set_name(Some("pdf ou image"));
+            filter.add_suffix("pdf");
+            filter.add_suffix("png");
+            filter.add_suffix("jpg");
+            filter.add_suffix("jpeg");
+            let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+            filters.append(&filter);
+
+            let dialog = gtk::FileDialog::builder()
+                .title("Pick attachment file")
+                .modal(true)
+                .filters(&filters)
+                .build();
+
+            let snd = sender.clone();
+            dialog.open(Some(&widgets.window),
+                Some(&Cancellable::new()),
+                move |file| {
+                    snd.input(AppMsg::AttachmentSelected(
+                        match file {
+                            Ok(gtk_file) => Some(gtk_file.path().unwrap()),
+                            Err(_) => None,
+                    }
+                    ));
+                },
+            );
+        }
+
         if model.show_signature_dialog {
             sender.input(AppMsg::ResetShowSignatureDialog);
             let dialog = gtk::FileDialog::builder()
@@ -281,11 +364,19 @@ impl SimpleComponent for AppModel {
                 AuthorFormOutput::PickSignature => AppMsg::PickSignature,
             });
 
+        let date = Local::now().date_naive();
+        let payment_terms = PaymentTerms::default();
+        // TODO: make it configurable
+        let devis_validity = 30;
+
         let billing_init = BillingInit {
-            dispense_name: match &cfg.last_dispense {
+            dispense_name: match cfg.dispenses.for_year(date.year()) {
                 Some(file_path) => file_path.to_str().unwrap().to_string(),
                 None => "".to_string(),
-            }
+            },
+            date,
+            payment_terms,
+            devis_validity,
         };
 
         let billing_view: Controller<BillingModel> =
@@ -294,17 +385,32 @@ impl SimpleComponent for AppModel {
             .forward(sender.input_sender(), |msg| match msg {
                 BillingOutput::Type(bill_type) => AppMsg::BillTypeChanged(bill_type),
                 BillingOutput::Number(number) => AppMsg::BillNumberChanged(number),
+                BillingOutput::Quote(quote) => AppMsg::QuoteChanged(quote),
                 BillingOutput::Nature(nature) => AppMsg::BillNature(nature),
                 BillingOutput::Diffuseur(is_diffuseur) => AppMsg::Diffuseur(is_diffuseur),
+                BillingOutput::Dispensed(is_dispensed) => AppMsg::Dispensed(is_dispensed),
                 BillingOutput::PickDispense => AppMsg::PickDispense,
+                BillingOutput::Date(date) => AppMsg::BillDate(date),
+                BillingOutput::PaymentTerms(terms) => AppMsg::PaymentTermsChanged(terms),
+                BillingOutput::DevisValidity(days) => AppMsg::DevisValidity(days),
+                BillingOutput::ServiceDate(service_date) => AppMsg::ServiceDateChanged(service_date),
+                BillingOutput::PickAttachment => AppMsg::PickAttachment,
+                BillingOutput::PreviewAttachment(path) => AppMsg::PreviewAttachment(path),
+                BillingOutput::Attachments(attachments) => AppMsg::AttachmentsEdited(attachments),
+                BillingOutput::RightsTransfer(rights_transfer) => AppMsg::RightsTransferEdited(rights_transfer),
             });
 
         let client_view: Controller<ClientViewModel> =
         ClientViewModel::builder()
-            .launch(cfg.clients.values().cloned().collect())
+            .launch({
+                let mut clients: Vec<Client> = cfg.clients.values().cloned().collect();
+                clients.sort_by(|a, b| a.name.cmp(&b.name));
+                clients
+            })
             .forward(sender.input_sender(), |msg| match msg {
                 ClientViewOutput::ClientListEdited(client_list) => AppMsg::ClientListEdited(client_list),
                 ClientViewOutput::Selected(client) => AppMsg::ClientEdited(client),
+                ClientViewOutput::Defaults(defaults) => AppMsg::ClientDefaults(defaults),
             });
 
         let products_view: Controller<ProductsModel> =
@@ -312,6 +418,21 @@ impl SimpleComponent for AppModel {
             .launch(())
             .forward(sender.input_sender(), AppMsg::ProductsEdited);
 
+        let ledger_view: Controller<LedgerModel> =
+        LedgerModel::builder()
+            .launch(())
+            .forward(sender.input_sender(), |_| AppMsg::PaymentsEdited);
+
+        let dashboard_view: Controller<DashboardModel> =
+        DashboardModel::builder()
+            .launch(())
+            .detach();
+
+        let declaration_view: Controller<DeclarationModel> =
+        DeclarationModel::builder()
+            .launch(())
+            .detach();
+
         let pdf_viewer = PdfViewerModel::builder()
             .launch(())
             .forward(sender.input_sender(), |_| { AppMsg::Null });
@@ -329,11 +450,15 @@ impl SimpleComponent for AppModel {
             billing_view,
             client_view,
             products_view,
+            ledger_view,
+            dashboard_view,
+            declaration_view,
             pdf_viewer,
             is_form_valid: false,
             status: UpToDate::None,
             show_signature_dialog: false,
             show_dispense_dialog: false,
+            show_attachment_dialog: false,
             show_pdf: true,
             pdf: None,
 
@@ -341,11 +466,18 @@ impl SimpleComponent for AppModel {
             bill_type: BillType::Facture,
             // TODO: use cfg number
             number: "000".to_string(),
+            quote: None,
+            date,
+            payment_terms,
+            devis_validity,
+            service_date: None,
             diffuseur: false,
-            dispense: cfg.last_dispense,
+            dispensed: true,
             nature: "".to_string(),
             client: Client::default(),
             products: Vec::new(),
+            attachments: Vec::new(),
+            rights_transfer: None,
         };
 
         let widgets = view_output!();
@@ -354,6 +486,8 @@ impl SimpleComponent for AppModel {
     }
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        let previewing_attachment = matches!(message, AppMsg::PreviewAttachment(_));
+
         match message {
             AppMsg::AuthorEdited(author) => {
                 self.status = UpToDate::None;
@@ -369,6 +503,10 @@ impl SimpleComponent for AppModel {
                 self.status = UpToDate::None;
                 self.client = client;
             }
+            AppMsg::ClientDefaults(defaults) => {
+                self.products_view.emit(ProductsInput::ApplyDefaults(defaults.products.clone()));
+                BILLING_DIALOG_BROKER.send(BillingInput::ApplyDefaults(defaults));
+            }
             AppMsg::BillTypeChanged(bill_type) => {
                 self.status = UpToDate::None;
                 self.bill_type = bill_type;
@@ -378,29 +516,52 @@ impl SimpleComponent for AppModel {
                 self.number = number;
                 CFG.lock().unwrap().last_facture = Some(self.number.clone());
             }
+            AppMsg::QuoteChanged(quote) => {
+                self.status = UpToDate::None;
+                self.quote = quote;
+            }
             AppMsg::BillNature(nature) => {
                 self.status = UpToDate::None;
                 self.nature = nature;
             }
+            AppMsg::BillDate(date) => {
+                self.status = UpToDate::None;
+                self.date = date;
+            }
+            AppMsg::PaymentTermsChanged(terms) => {
+                self.status = UpToDate::None;
+                self.payment_terms = terms;
+            }
+            AppMsg::DevisValidity(days) => {
+                self.status = UpToDate::None;
+                self.devis_validity = days;
+            }
+            AppMsg::ServiceDateChanged(service_date) => {
+                self.status = UpToDate::None;
+                self.service_date = service_date;
+            }
             AppMsg::Diffuseur(is_diffuseur) => {
                 self.status = UpToDate::None;
                 self.diffuseur = is_diffuseur;
             }
+            AppMsg::Dispensed(is_dispensed) => {
+                self.status = UpToDate::None;
+                self.dispensed = is_dispensed;
+            }
             AppMsg::SignatureSelected(filepath) => {
-                let signature = filepath.map(|filepath| filepath.to_str().unwrap().to_string());
+                let signature = filepath
+                    .map(files::import_or_keep)
+                    .map(|filepath| filepath.to_str().unwrap().to_string());
                 AUTHOR_DIALOG_BROKER.send(AuthorFormInput::Signature(signature));
             }
             AppMsg::DispenseSelected(filepath) => {
                 self.status = UpToDate::None;
-                self.dispense = filepath;
-                CFG.lock().unwrap().last_dispense = self.dispense.clone();
-                confy::store(APP_NAME, None, CFG.lock().unwrap().clone()).unwrap();
-
-                let filename = match &self.dispense {
-                    Some(filepath) => filepath.to_str().unwrap().to_string(),
-                    None => "".to_string(),
-                };
-                BILLING_DIALOG_BROKER.send(BillingInput::Dispense(filename));
+                if let Some(filepath) = filepath {
+                    // dispenses are valid for the civil year they're picked for
+                    let filepath = files::import_or_keep(filepath);
+                    CFG.lock().unwrap().dispenses.set(self.date.year(), filepath);
+                    confy::store(APP_NAME, None, CFG.lock().unwrap().clone()).unwrap();
+                }
             }
             AppMsg::PickSignature => {
                 self.show_signature_dialog = true
@@ -414,20 +575,57 @@ impl SimpleComponent for AppModel {
             AppMsg::ResetShowDispenseDialog => {
                 self.show_dispense_dialog = false;
             }
+            AppMsg::ResetShowAttachmentDialog => {
+                self.show_attachment_dialog = false;
+            }
+            AppMsg::PickAttachment => {
+                self.show_attachment_dialog = true;
+            }
+            AppMsg::AttachmentSelected(filepath) => {
+                if let Some(filepath) = filepath {
+                    let name = filepath.file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let imported = files::import_or_keep(filepath);
+                    match Attachment::new(name, &imported) {
+                        Some(attachment) => BILLING_DIALOG_BROKER.send(BillingInput::AddAttachment(attachment)),
+                        None => println!("Unsupported attachment: {}", imported.display()),
+                    }
+                }
+            }
+            AppMsg::AttachmentsEdited(attachments) => {
+                self.status = UpToDate::None;
+                self.attachments = attachments;
+            }
+            AppMsg::RightsTransferEdited(rights_transfer) => {
+                self.status = UpToDate::None;
+                self.rights_transfer = rights_transfer;
+            }
+            AppMsg::PaymentsEdited => {
+                self.dashboard_view.emit(DashboardInput::Reload);
+                self.declaration_view.emit(DeclarationInput::Reload);
+            }
+            AppMsg::PreviewAttachment(path) => {
+                self.show_pdf = true;
+                self.pdf_viewer.sender().emit(PdfViewerMsg::LoadPdf(path));
+            }
             AppMsg::ProductsEdited(products) => {
                 self.status = UpToDate::None;
+                self.client_view.emit(ClientViewInput::Products(products.clone()));
                 self.products = products;
             }
             // FIXME: use a menu to choose export destination
             // currently use a default destination that may not exist -> crash
             AppMsg::Export => {
                 // add client to the list of clients
-                CFG.lock().unwrap().clients.insert(self.client.name.clone(), self.client.clone());
+                CFG.lock().unwrap().clients.insert(self.client.id, self.client.clone());
+                CFG.lock().unwrap().mark_recent(self.client.id);
                 confy::store(APP_NAME, None, CFG.lock().unwrap().clone()).unwrap();
+                self.client_view.emit(ClientViewInput::RecentChanged);
 
                 // In case the pdf wasn't already compiled in background
                 if let UpToDate::None = self.status {
-                    self.pdf = Some(Template::new()
+                    self.pdf = Some(self.template()
                         .fill(self.invoice())
                         .expect("Error filling template")
                         .to_file(&CFG.lock().unwrap().tex_output_path)
@@ -442,18 +640,26 @@ impl SimpleComponent for AppModel {
                         .unwrap()
                         .export(&CFG.lock().unwrap().pdf_output_path)
                         .expect("Error copying pdf file");
-                    Template::new()
+                    self.template()
                         .fill(self.invoice())
                         .expect("Error filling template")
                         .to_file(&CFG.lock().unwrap().tex_output_path)
                         .expect("Error exporting tex file");
                     self.status = UpToDate::All;
                 }
+
+                // the pdf is already exported, a failure to archive it shouldn't lose the form
+                if let Err(e) = Archive::open().and_then(|archive| archive.store(&self.invoice())) {
+                    println!("Couldn't archive invoice {}: {e}", self.number);
+                }
+                self.ledger_view.emit(LedgerInput::Reload);
+                self.dashboard_view.emit(DashboardInput::Reload);
+                self.declaration_view.emit(DeclarationInput::Reload);
             }
             AppMsg::OpenPdf => {
                 // In case the pdf wasn't already compiled in background
                 if let UpToDate::None = self.status {
-                    self.pdf = Some(Template::new()
+                    self.pdf = Some(self.template()
                         .fill(self.invoice())
                         .expect("Error filling template")
                         .compile()
@@ -495,8 +701,23 @@ impl SimpleComponent for AppModel {
             },
         }
 
+        BILLING_DIALOG_BROKER.send(BillingInput::Contributions(self.contributions()));
+        BILLING_DIALOG_BROKER.send(BillingInput::Withholding(self.withholding()));
+        BILLING_DIALOG_BROKER.send(BillingInput::Dispense(match self.dispense() {
+            Some(filepath) => filepath.to_str().unwrap().to_string(),
+            None => "".to_string(),
+        }));
+        BILLING_DIALOG_BROKER.send(BillingInput::DispenseWarning(
+            CFG.lock().unwrap().dispenses.warning(self.date, Local::now().date_naive())
+        ));
+
         self.is_form_valid = self.form_valid();
 
+        // keep showing the attachment until something else happens
+        if previewing_attachment {
+            return;
+        }
+
         if self.is_form_valid && self.show_pdf {
             if self.status == UpToDate::None {
                 self.queue_compilation(sender.clone());
@@ -519,7 +740,10 @@ impl AppModel {
         if !self.client.valid() { return false; }
         if self.products.is_empty() { return false; }
         if self.products.iter().any(|prod| prod.name.is_empty()) { return false; }
-        if self.diffuseur && self.dispense.is_none() { return false; }
+        if self.products.iter().any(|prod| prod.service_date.is_some_and(|date| !date.valid())) { return false; }
+        if self.service_date.is_some_and(|date| !date.valid()) { return false; }
+        if self.diffuseur && self.dispensed && self.dispense().is_none() { return false; }
+        if self.rights_transfer.as_ref().is_some_and(|rights| !rights.valid()) { return false; }
         true
     }
 
@@ -531,15 +755,51 @@ impl AppModel {
             client: self.client.clone(),
             nature: self.nature.clone(),
             diffuseur: self.diffuseur,
-            dispense_path: match &self.dispense {
+            contributions: self.contributions(),
+            withholding: self.withholding(),
+            dispense_path: match &self.dispense() {
                 Some(path_buf) => path_buf.to_str().unwrap().to_string(),
                 None => "".to_string(),
             },
             products: self.products.clone(),
-            date: Local::now().date_naive().format("%d/%m/%Y").to_string(),
+            // invoices are issued under the franchise en base (article 293 B du CGI)
+            vat: Money::ZERO,
+            date: self.date,
+            payment_terms: self.payment_terms,
+            due_date: self.payment_terms.due_date(self.date),
+            devis_validity: self.devis_validity,
+            quote: match self.bill_type {
+                BillType::Facture => self.quote.clone(),
+                BillType::Devis => None,
+            },
+            service_date: self.service_date,
+            legal: self.author.legal.clone(),
+            attachments: self.attachments.clone(),
+            rights_transfer: self.rights_transfer.clone(),
+            remuneration_split: self.rights_transfer.as_ref().map(|rights| {
+                rights.split(self.products.iter().map(|product| product.total).sum())
+            }),
+            payment_qr_path: None,
         }
     }
 
+    fn contributions(&self) -> Option<DiffuseurContributions> {
+        if !self.diffuseur { return None; }
+        let rates = CFG.lock().unwrap().contribution_rates(self.date.year());
+        Some(DiffuseurContributions::compute(&self.products, rates))
+    }
+
+    /// dispense de précompte valid at the invoice date
+    fn dispense(&self) -> Option<PathBuf> {
+        CFG.lock().unwrap().dispenses.for_date(self.date).map(|path| path.to_path_buf())
+    }
+
+    fn withholding(&self) -> Option<Withholding> {
+        if !self.diffuseur || self.dispensed { return None; }
+        let rates = CFG.lock().unwrap().contribution_rates(self.date.year());
+        Some(Withholding::compute(&self.products, rates))
+    }
+
     fn queue_compilation(&mut self, sender: ComponentSender<Self>) {
         dbg!(self.last_compilation.elapsed().as_secs());
         dbg!(self.compile_cooldown.as_secs());
@@ -553,13 +813,25 @@ impl AppModel {
         }
     }
 
+    /// template preferred by the client, or the bundled one
+    fn template(&self) -> Template {
+        match &self.client.defaults.template {
+            Some(path) => Template::from(path).unwrap_or_else(|e| {
+                println!("Couldn't read template {path}, using the default one: {e}");
+                Template::new()
+            }),
+            None => Template::new(),
+        }
+    }
+
     // FIXME: queue compile orders and only run the last one of the queue
     //        once the previous one is finished
     fn compile_pdf_in_background(&self, sender: ComponentSender<Self>) {
         let invoice_data = self.invoice();
+        let template = self.template();
 
         std::thread::spawn(move || {
-            match Template::new()
+            match template
                 .fill(invoice_data)
                 .and_then(|template| template.compile()) {
                     Ok(pdf_file) => sender.input(AppMsg::PdfCompiled(pdf_file)),
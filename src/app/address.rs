@@ -6,7 +6,8 @@ pub struct Address {
     pub number_and_street: String,
     pub postcode: String,
     pub city: String,
-    // country: String,
+    #[serde(default)]
+    pub country: String,
 }
 impl Address {
     pub(crate) fn valid(&self) -> bool {
@@ -20,6 +21,10 @@ impl Address {
 impl fmt::Display for Address {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Customize so only `x` and `y` are denoted.
-        write!(f, "{}\n{} {}", self.number_and_street, self.postcode, self.city)
+        write!(f, "{}\n{} {}", self.number_and_street, self.postcode, self.city)?;
+        if !self.country.is_empty() {
+            write!(f, "\n{}", self.country)?;
+        }
+        Ok(())
     }
 }
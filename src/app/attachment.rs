@@ -0,0 +1,38 @@
+use std::fmt;
+use std::path::Path;
+
+/// file appended to the invoice as annex pages
+#[derive(Debug,Clone,PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Attachment {
+    /// displayed name, usually the original file name
+    pub name: String,
+    pub path: String,
+    pub kind: AttachmentKind,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub enum AttachmentKind {
+    Pdf,
+    Image,
+}
+
+impl Attachment {
+    /// only pdfs and images supported by latex can be attached
+    pub fn new(name: String, path: &Path) -> Option<Attachment> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        let kind = match extension.as_str() {
+            "pdf" => AttachmentKind::Pdf,
+            "png" | "jpg" | "jpeg" => AttachmentKind::Image,
+            _ => return None,
+        };
+        Some(Attachment { name, path: path.to_str()?.to_string(), kind })
+    }
+}
+
+impl fmt::Display for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
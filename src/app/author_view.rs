@@ -3,6 +3,9 @@ use std::fmt;
 use adw::prelude::*;
 use relm4::prelude::*;
 
+use crate::money::Money;
+use crate::validation;
+
 
 pub type AuthorName = String;
 
@@ -36,15 +39,60 @@ pub struct Author {
     pub ape: String,
     pub email: Option<String>,
     pub iban: Option<String>,
+    #[serde(default)]
+    pub bic: Option<String>,
+    /// name of the bank, printed with the payment details
+    #[serde(default)]
+    pub bank: Option<String>,
+    /// EPC QR code of the transfer printed on invoices, needs the IBAN
+    #[serde(default)]
+    pub payment_qr: bool,
     pub signature_file_name: Option<String>,
+    #[serde(default)]
+    pub legal: LegalTerms,
 }
 impl Author {
     pub(crate) fn valid(&self) -> bool {
         !self.title.is_empty() &&
         !self.name.is_empty()  &&
         self.address.valid()   &&
-        !self.siret.is_empty() &&
-        !self.ape.is_empty()
+        validation::siret(&self.siret).is_ok() &&
+        validation::ape(&self.ape).is_ok() &&
+        self.iban.as_deref().is_none_or(|iban| validation::iban(iban).is_ok()) &&
+        self.bic.as_deref().is_none_or(|bic| validation::bic(bic).is_ok())
+    }
+
+    fn iban_error(&self) -> String {
+        self.iban.as_deref().and_then(|iban| validation::iban(iban).err()).unwrap_or_default()
+    }
+
+    fn bic_error(&self) -> String {
+        self.bic.as_deref().and_then(|bic| validation::bic(bic).err()).unwrap_or_default()
+    }
+}
+
+/// late payment and legal clauses printed at the bottom of invoices
+#[derive(Debug,Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LegalTerms {
+    /// late payment penalty rate in %
+    pub penalty_rate: f64,
+    /// fixed indemnity for recovery costs in €
+    pub recovery_fee: Money,
+    /// early payment discount terms (escompte)
+    pub discount: String,
+    /// free text, e.g. "TVA non applicable, art. 293 B du CGI"
+    pub mentions: String,
+}
+impl Default for LegalTerms {
+    fn default() -> Self {
+        Self {
+            penalty_rate: 10.0,
+            recovery_fee: Money::from_cents(4000),
+            discount: "Pas d'escompte pour paiement anticipé.".to_string(),
+            mentions: "".to_string(),
+        }
     }
 }
 
@@ -57,6 +105,10 @@ impl fmt::Display for Author {
 
 pub struct AuthorFormModel {
     author: Author,
+    /// raw text of the penalty rate entry, may not be a valid number
+    penalty_rate: String,
+    /// raw text of the recovery fee entry, may not be a valid number
+    recovery_fee: String,
 }
 
 #[derive(Debug)]
@@ -78,7 +130,14 @@ pub enum AuthorFormInput {
     EmailEdited(String),
     // TVAEdited(String),
     IbanEdited(String),
+    BicEdited(String),
+    BankEdited(String),
+    PaymentQr(bool),
     Signature(Option<String>),
+    PenaltyRateEdited(String),
+    RecoveryFeeEdited(String),
+    DiscountEdited(String),
+    MentionsEdited(String),
 }
 
 /// entry_row!("Name", NameEdited)
@@ -155,7 +214,13 @@ impl SimpleComponent for AuthorFormModel {
                 add = &adw::EntryRow {
                     set_title: "SIRET *",
                     set_text: &model.author.siret,
-                    #[watch] set_css_classes: if model.author.siret.is_empty() { &["error"] } else { &[""] },
+                    #[watch] set_css_classes: if validation::siret(&model.author.siret).is_err() { &["error"] } else { &[""] },
+
+                    add_suffix = &gtk::Label {
+                        add_css_class: "error",
+                        add_css_class: "caption",
+                        #[watch] set_label: &validation::siret(&model.author.siret).err().unwrap_or_default(),
+                    },
 
                     connect_changed[sender] => move |entry_row| {
                         sender.input(AuthorFormInput::SiretEdited(entry_row.property("text")));
@@ -164,7 +229,13 @@ impl SimpleComponent for AuthorFormModel {
                 add = &adw::EntryRow {
                     set_title: "Code APE *",
                     set_text: &model.author.ape,
-                    #[watch] set_css_classes: if model.author.ape.is_empty() { &["error"] } else { &[""] },
+                    #[watch] set_css_classes: if validation::ape(&model.author.ape).is_err() { &["error"] } else { &[""] },
+
+                    add_suffix = &gtk::Label {
+                        add_css_class: "error",
+                        add_css_class: "caption",
+                        #[watch] set_label: &validation::ape(&model.author.ape).err().unwrap_or_default(),
+                    },
 
                     connect_changed[sender] => move |entry_row| {
                         sender.input(AuthorFormInput::ApeEdited(entry_row.property("text")));
@@ -180,10 +251,49 @@ impl SimpleComponent for AuthorFormModel {
                 add = &adw::EntryRow {
                     set_title: "IBAN",
                     set_text: &model.author.iban.clone().unwrap_or_default(),
+                    #[watch] set_css_classes: if !model.author.iban_error().is_empty() { &["error"] } else { &[""] },
+
+                    add_suffix = &gtk::Label {
+                        add_css_class: "error",
+                        add_css_class: "caption",
+                        #[watch] set_label: &model.author.iban_error(),
+                    },
+
                     connect_changed[sender] => move |entry_row| {
                         sender.input(AuthorFormInput::IbanEdited(entry_row.property("text")));
                     }
                 },
+                add = &adw::EntryRow {
+                    set_title: "BIC",
+                    set_text: &model.author.bic.clone().unwrap_or_default(),
+                    #[watch] set_css_classes: if !model.author.bic_error().is_empty() { &["error"] } else { &[""] },
+
+                    add_suffix = &gtk::Label {
+                        add_css_class: "error",
+                        add_css_class: "caption",
+                        #[watch] set_label: &model.author.bic_error(),
+                    },
+
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(AuthorFormInput::BicEdited(entry_row.property("text")));
+                    }
+                },
+                add = &adw::EntryRow {
+                    set_title: "Banque",
+                    set_text: &model.author.bank.clone().unwrap_or_default(),
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(AuthorFormInput::BankEdited(entry_row.property("text")));
+                    }
+                },
+                add = &adw::SwitchRow {
+                    set_title: "QR code de paiement",
+                    set_subtitle: "Virement SEPA pré-rempli sur les factures",
+                    set_active: model.author.payment_qr,
+                    #[watch] set_sensitive: model.author.iban.is_some(),
+                    connect_active_notify[sender] => move |switch| {
+                        sender.input(AuthorFormInput::PaymentQr(switch.is_active()));
+                    },
+                },
             },
             add = &adw::PreferencesGroup {
                 add = &adw::ActionRow {
@@ -199,6 +309,57 @@ impl SimpleComponent for AuthorFormModel {
                         }
                 },
             },
+            add = &adw::PreferencesGroup {
+                set_title: "Mentions légales",
+
+                add = &adw::EntryRow {
+                    set_title: "Pénalités de retard (%)",
+                    set_text: &model.author.legal.penalty_rate.to_string(),
+                    set_input_purpose: gtk::InputPurpose::Number,
+                    #[watch] set_css_classes: if parse_float(&model.penalty_rate).is_none() { &["error"] } else { &[""] },
+
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(AuthorFormInput::PenaltyRateEdited(entry_row.property("text")));
+                    }
+                },
+                add = &adw::EntryRow {
+                    set_title: "Indemnité forfaitaire de recouvrement (€)",
+                    set_text: &model.author.legal.recovery_fee.to_string(),
+                    set_input_purpose: gtk::InputPurpose::Number,
+                    #[watch] set_css_classes: if model.recovery_fee.parse::<Money>().is_err() { &["error"] } else { &[""] },
+
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(AuthorFormInput::RecoveryFeeEdited(entry_row.property("text")));
+                    }
+                },
+                add = &adw::EntryRow {
+                    set_title: "Escompte",
+                    set_text: &model.author.legal.discount,
+
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(AuthorFormInput::DiscountEdited(entry_row.property("text")));
+                    }
+                },
+            },
+            add = &adw::PreferencesGroup {
+                set_title: "Autres mentions",
+                set_description: Some("ex: TVA non applicable, art. 293 B du CGI"),
+
+                add = &gtk::TextView {
+                    set_height_request: 100,
+                    set_wrap_mode: gtk::WrapMode::Word,
+                    inline_css: "border-radius: 14px; padding: 10px",
+
+                    #[wrap(Some)]
+                    set_buffer = &gtk::TextBuffer {
+                        set_text: &model.author.legal.mentions,
+
+                        connect_changed[sender] => move |buffer| {
+                            sender.input(AuthorFormInput::MentionsEdited(buffer.property("text")));
+                        }
+                    }
+                },
+            },
         },
     }
 
@@ -208,8 +369,11 @@ impl SimpleComponent for AuthorFormModel {
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
 
+        let author = author.unwrap_or_default();
         let model = AuthorFormModel {
-            author: author.unwrap_or_default(),
+            penalty_rate: author.legal.penalty_rate.to_string(),
+            recovery_fee: author.legal.recovery_fee.to_string(),
+            author,
         };
         let widgets = view_output!();
 
@@ -231,8 +395,30 @@ impl SimpleComponent for AuthorFormModel {
             AuthorFormInput::IbanEdited(value) => {
                 self.author.iban = if value.is_empty() { None } else { Some(value) }
             }
+            AuthorFormInput::BicEdited(value) => {
+                self.author.bic = if value.is_empty() { None } else { Some(value) }
+            }
+            AuthorFormInput::BankEdited(value) => {
+                self.author.bank = if value.is_empty() { None } else { Some(value) }
+            }
+            AuthorFormInput::PaymentQr(active) => self.author.payment_qr = active,
             AuthorFormInput::Signature(signature) => self.author.signature_file_name = signature,
+            AuthorFormInput::PenaltyRateEdited(value) => {
+                if let Some(rate) = parse_float(&value) { self.author.legal.penalty_rate = rate }
+                self.penalty_rate = value;
+            }
+            AuthorFormInput::RecoveryFeeEdited(value) => {
+                if let Ok(fee) = value.parse::<Money>() { self.author.legal.recovery_fee = fee }
+                self.recovery_fee = value;
+            }
+            AuthorFormInput::DiscountEdited(value) => self.author.legal.discount = value,
+            AuthorFormInput::MentionsEdited(value) => self.author.legal.mentions = value,
         }
         sender.output(AuthorFormOutput::AuthorEdited(self.author.clone())).unwrap();
     }
 }
+
+/// parse a number written with either a . or a , as decimal separator
+fn parse_float(value: &str) -> Option<f64> {
+    value.trim().replace(",", ".").parse::<f64>().ok()
+}
@@ -1,18 +1,50 @@
 use adw::prelude::*;
+use chrono::{Datelike, Days, NaiveDate};
 use relm4::prelude::*;
 
+use crate::app::date_row::{DateRowInit, DateRowInput, DateRowModel};
+use crate::app::{Attachment, ClientDefaults, RightsTransfer, ServiceDate};
+use crate::contributions::{DiffuseurContributions, Withholding};
 use crate::CFG;
 
+mod attachment_row;
+
 // FIXME: use different tabs for facture and devis
 
-#[derive(Default,Clone)]
 pub struct BillingModel {
     bill_type: BillType,
     number: String,
     last_number: Option<String>,
+    /// number of the devis accepted by the facture, empty if none
+    quote: String,
+    /// the facture was made from the devis being edited, the quote row needs to be updated
+    quote_changed: bool,
     nature: String,
     diffuseur: bool,
+    /// the author has a dispense de précompte, otherwise contributions are withheld
+    dispensed: bool,
     dispense_file_name: String,
+    /// missing or expiring dispense
+    dispense_warning: Option<String>,
+    contributions: Option<DiffuseurContributions>,
+    withholding: Option<Withholding>,
+    date: NaiveDate,
+    payment_terms: PaymentTerms,
+    /// last number of days entered, restored when switching back to `PaymentTerms::Days`
+    payment_days: u32,
+    devis_validity: u32,
+    date_row: Controller<DateRowModel>,
+    due_date_row: Controller<DateRowModel>,
+    has_service_date: bool,
+    has_service_end: bool,
+    service_date: ServiceDate,
+    service_start_row: Controller<DateRowModel>,
+    service_end_row: Controller<DateRowModel>,
+    attachments: FactoryVecDeque<attachment_row::Model>,
+    has_rights_transfer: bool,
+    rights_transfer: RightsTransfer,
+    /// client defaults were applied, widgets need to be updated
+    applying_defaults: bool,
 }
 
 #[derive(Debug,Default,Clone)]
@@ -23,28 +55,122 @@ pub enum BillType {
     Devis,
 }
 
+/// when the invoice has to be paid
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum PaymentTerms {
+    /// number of days after the invoice date
+    Days(u32),
+    /// last day of the invoice month
+    EndOfMonth,
+    OnReceipt,
+    Custom(NaiveDate),
+}
+pub(crate) const DEFAULT_PAYMENT_DAYS: u32 = 30;
+
+impl Default for PaymentTerms {
+    fn default() -> Self {
+        PaymentTerms::Days(DEFAULT_PAYMENT_DAYS)
+    }
+}
+impl PaymentTerms {
+    /// labels of the payment terms combo row, in the same order as `index`
+    pub(crate) const LABELS: [&'static str; 4] = ["Nombre de jours", "Fin de mois", "À réception", "Date personnalisée"];
+
+    pub fn due_date(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            PaymentTerms::Days(days) => date + Days::new((*days).into()),
+            PaymentTerms::EndOfMonth => {
+                let first_of_next_month = if date.month() == 12 {
+                    NaiveDate::from_ymd_opt(date.year() + 1, 1, 1)
+                } else {
+                    NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1)
+                };
+                first_of_next_month.and_then(|d| d.pred_opt()).unwrap_or(date)
+            },
+            PaymentTerms::OnReceipt => date,
+            PaymentTerms::Custom(due_date) => *due_date,
+        }
+    }
+
+    pub(crate) fn index(&self) -> u32 {
+        match self {
+            PaymentTerms::Days(_) => 0,
+            PaymentTerms::EndOfMonth => 1,
+            PaymentTerms::OnReceipt => 2,
+            PaymentTerms::Custom(_) => 3,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct BillingInit {
     /// dispense file name
     pub dispense_name: String,
+    pub date: NaiveDate,
+    pub payment_terms: PaymentTerms,
+    /// devis validity in days
+    pub devis_validity: u32,
 }
 
 #[derive(Debug)]
 pub enum BillingInput {
     Type(BillType),
     Diffuseur(bool),
+    Dispensed(bool),
     Number(String),
+    Quote(String),
+    Nature(String),
+    /// defaults of the picked client
+    ApplyDefaults(ClientDefaults),
     /// dispense file name
     Dispense(String),
+    DispenseWarning(Option<String>),
+    Date(NaiveDate),
+    /// index of the selected payment terms in the combo row
+    PaymentTermsKind(u32),
+    PaymentDays(u32),
+    DueDate(NaiveDate),
+    DevisValidity(u32),
+    HasServiceDate(bool),
+    HasServiceEnd(bool),
+    ServiceStart(NaiveDate),
+    ServiceEnd(NaiveDate),
+    /// contributions computed from the products, if diffuseur
+    Contributions(Option<DiffuseurContributions>),
+    /// withheld contributions computed from the products, if précompte
+    Withholding(Option<Withholding>),
+    AddAttachment(Attachment),
+    PreviewAttachment(DynamicIndex),
+    MoveAttachmentUp(DynamicIndex),
+    DeleteAttachment(DynamicIndex),
+    HasRightsTransfer(bool),
+    RightsMedia(String),
+    RightsTerritory(String),
+    RightsDuration(String),
+    RightsExclusive(bool),
+    RightsShare(f64),
 }
 
 #[derive(Debug)]
 pub enum BillingOutput {
     Type(BillType),
     Number(String),
+    Quote(Option<String>),
     Nature(String),
     Diffuseur(bool),
+    Dispensed(bool),
     PickDispense,
+    Date(NaiveDate),
+    PaymentTerms(PaymentTerms),
+    DevisValidity(u32),
+    ServiceDate(Option<ServiceDate>),
+    PickAttachment,
+    /// path of the attachment to preview
+    PreviewAttachment(String),
+    Attachments(Vec<Attachment>),
+    RightsTransfer(Option<RightsTransfer>),
 }
 
 #[relm4::component(pub)]
@@ -132,30 +258,233 @@ impl SimpleComponent for BillingModel {
                         sender.input(BillingInput::Number(entry_row.property("text")));
                     },
                 },
+
+                add = &adw::EntryRow {
+                    set_title: "Devis accepté (n°)",
+                    set_tooltip: "rempli lorsque la facture est faite à partir d'un devis",
+                    #[watch] set_visible: matches!(model.bill_type, BillType::Facture),
+
+                    #[track(model.quote_changed)]
+                    #[block_signal(quote_handler)]
+                    set_text: &model.quote,
+
+                    connect_changed[sender] => move |entry_row| {
+                        sender.input(BillingInput::Quote(entry_row.text().to_string()));
+                    } @quote_handler,
+                },
+
+                add = model.date_row.widget(),
+
+                add = &adw::ExpanderRow {
+                    set_title: "Date de prestation",
+                    set_subtitle: "si différente de la date de facturation",
+                    set_show_enable_switch: true,
+                    set_enable_expansion: model.has_service_date,
+
+                    connect_enable_expansion_notify[sender] => move |row| {
+                        sender.input(BillingInput::HasServiceDate(row.enables_expansion()));
+                    },
+
+                    add_row = model.service_start_row.widget(),
+
+                    add_row = &adw::SwitchRow {
+                        set_title: "Période",
+                        set_active: model.has_service_end,
+
+                        connect_active_notify[sender] => move |switch| {
+                            sender.input(BillingInput::HasServiceEnd(switch.is_active()));
+                        },
+                    },
+
+                    add_row = model.service_end_row.widget(),
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                #[watch] set_title: match model.bill_type {
+                    BillType::Facture => "Règlement",
+                    BillType::Devis => "Validité",
+                },
+
+                add = &adw::ComboRow {
+                    set_title: "Échéance",
+                    set_model: Some(&gtk::StringList::new(&PaymentTerms::LABELS)),
+                    #[track(model.applying_defaults)]
+                    #[block_signal(payment_terms_handler)]
+                    set_selected: model.payment_terms.index(),
+                    #[watch] set_visible: matches!(model.bill_type, BillType::Facture),
+
+                    connect_selected_notify[sender] => move |row| {
+                        sender.input(BillingInput::PaymentTermsKind(row.selected()));
+                    } @payment_terms_handler,
+                },
+
+                add = &adw::SpinRow {
+                    set_title: "Délai de paiement (jours)",
+                    set_adjustment: Some(&gtk::Adjustment::builder()
+                        .lower(0.0)
+                        .upper(365.0)
+                        .value(model.payment_days.into())
+                        .step_increment(1.0)
+                        .page_increment(10.0)
+                        .build()),
+                    #[watch] set_visible: matches!(model.bill_type, BillType::Facture) && matches!(model.payment_terms, PaymentTerms::Days(_)),
+                    #[track(model.applying_defaults)]
+                    #[block_signal(payment_days_handler)]
+                    set_value: model.payment_days.into(),
+
+                    connect_changed[sender] => move |row| {
+                        sender.input(BillingInput::PaymentDays(row.value() as u32));
+                    } @payment_days_handler,
+                },
+
+                add = model.due_date_row.widget(),
+
+                add = &adw::SpinRow {
+                    set_title: "Durée de validité du devis (jours)",
+                    set_adjustment: Some(&gtk::Adjustment::builder()
+                        .lower(1.0)
+                        .upper(365.0)
+                        .value(model.devis_validity.into())
+                        .step_increment(1.0)
+                        .page_increment(10.0)
+                        .build()),
+                    #[watch] set_visible: matches!(model.bill_type, BillType::Devis),
+
+                    connect_changed[sender] => move |row| {
+                        sender.input(BillingInput::DevisValidity(row.value() as u32));
+                    },
+                },
             },
 
             add = &adw::PreferencesGroup {
                 set_margin_top: 25,
                 add = &adw::SwitchRow {
                     set_title: "Contributions Diffuseur",
+                    #[track(model.applying_defaults)]
+                    #[block_signal(diffuseur_handler)]
+                    set_active: model.diffuseur,
                     connect_active_notify[sender] => move |switch| {
                         sender.input(BillingInput::Diffuseur(switch.is_active()));
+                    } @diffuseur_handler,
+                },
+                add = &adw::SwitchRow {
+                    set_title: "Dispensé de précompte",
+                    set_subtitle: "sinon les cotisations sont précomptées par le diffuseur",
+                    set_active: model.dispensed,
+                    #[watch]
+                    set_visible: model.diffuseur,
+                    connect_active_notify[sender] => move |switch| {
+                        sender.input(BillingInput::Dispensed(switch.is_active()));
                     }
                 },
                 add = &adw::ActionRow {
-                        set_title: "Dispense de précompte",
+                        #[watch]
+                        set_title: &format!("Dispense de précompte {}", model.date.year()),
                         #[watch]
                         set_subtitle: &model.dispense_file_name,
                         #[watch]
                         set_css_classes: if model.dispense_file_name.is_empty() { &["error"] } else { &[""] },
                         #[watch]
-                        set_visible: model.diffuseur,
+                        set_visible: model.diffuseur && model.dispensed,
                         add_suffix = &gtk::Button {
                             set_margin_all: 10,
                             set_icon_name: "document-open-symbolic",
                             connect_clicked[sender] => move |_| sender.output(BillingOutput::PickDispense).unwrap(),
                         }
                 },
+                add = &adw::ActionRow {
+                    #[watch]
+                    set_title: model.dispense_warning.as_deref().unwrap_or_default(),
+                    add_css_class: "warning",
+                    #[watch]
+                    set_visible: model.diffuseur && model.dispensed && model.dispense_warning.is_some(),
+                    add_prefix = &gtk::Image {
+                        set_icon_name: Some("dialog-warning-symbolic"),
+                    },
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                set_title: "Contributions diffuseur (client)",
+                set_description: Some("dues à l'Urssaf par le diffuseur"),
+                #[watch] set_visible: model.contributions.is_some(),
+
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("Sécurité sociale ({} %)",
+                        model.contributions.map(|c| c.rates.social_security).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.contributions.map(|c| c.social_security).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("Formation professionnelle ({} %)",
+                        model.contributions.map(|c| c.rates.professional_training).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.contributions.map(|c| c.professional_training).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: "Total",
+                    add_css_class: "property",
+                    add_suffix = &gtk::Label {
+                        add_css_class: "heading",
+                        #[watch] set_label: &format!("{} €",
+                            model.contributions.map(|c| c.total).unwrap_or_default()),
+                    },
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                set_title: "Précompte",
+                set_description: Some("cotisations retenues par le diffuseur"),
+                #[watch] set_visible: model.withholding.is_some(),
+
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("CSG ({} % de {} % du brut)",
+                        model.withholding.map(|w| w.rates.csg).unwrap_or_default(),
+                        model.withholding.map(|w| w.rates.csg_crds_base).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.withholding.map(|w| w.csg).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("CRDS ({} % de {} % du brut)",
+                        model.withholding.map(|w| w.rates.crds).unwrap_or_default(),
+                        model.withholding.map(|w| w.rates.csg_crds_base).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.withholding.map(|w| w.crds).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("Vieillesse plafonnée ({} %)",
+                        model.withholding.map(|w| w.rates.vieillesse_plafonnee).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.withholding.map(|w| w.vieillesse_plafonnee).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    #[watch] set_title: &format!("Vieillesse déplafonnée ({} %)",
+                        model.withholding.map(|w| w.rates.vieillesse_deplafonnee).unwrap_or_default()),
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €",
+                            model.withholding.map(|w| w.vieillesse_deplafonnee).unwrap_or_default()),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: "Net à payer",
+                    add_css_class: "property",
+                    add_suffix = &gtk::Label {
+                        add_css_class: "heading",
+                        #[watch] set_label: &format!("{} €",
+                            model.withholding.map(|w| w.net).unwrap_or_default()),
+                    },
+                },
             },
 
             add = &adw::PreferencesGroup {
@@ -168,12 +497,91 @@ impl SimpleComponent for BillingModel {
 
                     #[wrap(Some)]
                     set_buffer = &gtk::TextBuffer {
+                        #[track(model.applying_defaults)]
+                        #[block_signal(nature_handler)]
+                        set_text: &model.nature,
                         connect_changed[sender] => move |entry| {
-                            let _ = sender.output(BillingOutput::Nature(entry.property("text")));
-                        }
+                            sender.input(BillingInput::Nature(entry.property("text")));
+                        } @nature_handler,
                     }
                 },
             },
+
+            add = &adw::PreferencesGroup {
+                add = &adw::ExpanderRow {
+                    set_title: "Cession de droits d'auteur",
+                    set_subtitle: "ajoutée en annexe",
+                    set_show_enable_switch: true,
+                    set_enable_expansion: model.has_rights_transfer,
+
+                    connect_enable_expansion_notify[sender] => move |row| {
+                        sender.input(BillingInput::HasRightsTransfer(row.enables_expansion()));
+                    },
+
+                    add_row = &adw::EntryRow {
+                        set_title: "Supports et modes d'exploitation *",
+                        set_text: &model.rights_transfer.media,
+                        #[watch] set_css_classes: if model.rights_transfer.media.is_empty() { &["error"] } else { &[""] },
+
+                        connect_changed[sender] => move |entry_row| {
+                            sender.input(BillingInput::RightsMedia(entry_row.property("text")));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: "Territoire *",
+                        set_text: &model.rights_transfer.territory,
+                        #[watch] set_css_classes: if model.rights_transfer.territory.is_empty() { &["error"] } else { &[""] },
+
+                        connect_changed[sender] => move |entry_row| {
+                            sender.input(BillingInput::RightsTerritory(entry_row.property("text")));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: "Durée *",
+                        set_text: &model.rights_transfer.duration,
+                        #[watch] set_css_classes: if model.rights_transfer.duration.is_empty() { &["error"] } else { &[""] },
+
+                        connect_changed[sender] => move |entry_row| {
+                            sender.input(BillingInput::RightsDuration(entry_row.property("text")));
+                        },
+                    },
+                    add_row = &adw::SwitchRow {
+                        set_title: "Exclusivité",
+                        set_active: model.rights_transfer.exclusive,
+
+                        connect_active_notify[sender] => move |switch| {
+                            sender.input(BillingInput::RightsExclusive(switch.is_active()));
+                        },
+                    },
+                    add_row = &adw::SpinRow {
+                        set_title: "Part de la rémunération au titre de la cession (%)",
+                        set_adjustment: Some(&gtk::Adjustment::builder()
+                            .lower(0.0)
+                            .upper(100.0)
+                            .value(model.rights_transfer.rights_share)
+                            .step_increment(1.0)
+                            .page_increment(10.0)
+                            .build()),
+
+                        connect_changed[sender] => move |row| {
+                            sender.input(BillingInput::RightsShare(row.value()));
+                        },
+                    },
+                },
+            },
+
+            #[local_ref]
+            add = attachments_box -> adw::PreferencesGroup {
+                set_title: "Annexes",
+                set_description: Some("pdf ou images ajoutés à la fin du document"),
+
+                #[wrap(Some)]
+                set_header_suffix = &gtk::Button {
+                    set_tooltip: "Ajouter une annexe",
+                    set_icon_name: "list-add-symbolic",
+                    connect_clicked[sender] => move |_| sender.output(BillingOutput::PickAttachment).unwrap(),
+                },
+            },
         }
     }
 
@@ -182,19 +590,84 @@ impl SimpleComponent for BillingModel {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let date_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Date".to_string(), date: params.date })
+            .forward(sender.input_sender(), BillingInput::Date);
+
+        let due_date_row = DateRowModel::builder()
+            .launch(DateRowInit {
+                title: "Date d'échéance".to_string(),
+                date: params.payment_terms.due_date(params.date),
+            })
+            .forward(sender.input_sender(), BillingInput::DueDate);
+
+        let service_start_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Début".to_string(), date: params.date })
+            .forward(sender.input_sender(), BillingInput::ServiceStart);
+
+        let service_end_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Fin".to_string(), date: params.date })
+            .forward(sender.input_sender(), BillingInput::ServiceEnd);
+
+        let attachments = FactoryVecDeque::builder()
+            .launch(adw::PreferencesGroup::default())
+            .forward(sender.input_sender(), |output| match output {
+                attachment_row::AttachmentOutput::Preview(index) => BillingInput::PreviewAttachment(index),
+                attachment_row::AttachmentOutput::MoveUp(index) => BillingInput::MoveAttachmentUp(index),
+                attachment_row::AttachmentOutput::Delete(index) => BillingInput::DeleteAttachment(index),
+            });
+
         let model = BillingModel {
-            dispense_file_name: params.dispense_name,
+            bill_type: BillType::default(),
+            number: "".to_string(),
             last_number: CFG.lock().unwrap().last_facture.clone(),
-            ..BillingModel::default()
+            quote: "".to_string(),
+            quote_changed: false,
+            nature: "".to_string(),
+            diffuseur: false,
+            dispensed: true,
+            dispense_file_name: params.dispense_name,
+            dispense_warning: None,
+            contributions: None,
+            withholding: None,
+            date: params.date,
+            payment_terms: params.payment_terms,
+            payment_days: match params.payment_terms {
+                PaymentTerms::Days(days) => days,
+                _ => DEFAULT_PAYMENT_DAYS,
+            },
+            devis_validity: params.devis_validity,
+            date_row,
+            due_date_row,
+            has_service_date: false,
+            has_service_end: false,
+            service_date: ServiceDate { start: params.date, end: None },
+            service_start_row,
+            service_end_row,
+            attachments,
+            has_rights_transfer: false,
+            rights_transfer: RightsTransfer::default(),
+            applying_defaults: false,
         };
+        model.update_due_date_row();
+        model.service_end_row.widget().set_sensitive(model.has_service_end);
 
+        let attachments_box = model.attachments.widget();
         let widgets = view_output!();
         ComponentParts { model, widgets }
     }
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        self.applying_defaults = false;
+        self.quote_changed = false;
         match message {
             BillingInput::Type(bill_type) => {
+                // a devis turned into a facture is accepted
+                if matches!((&self.bill_type, &bill_type), (BillType::Devis, BillType::Facture)) && !self.number.is_empty() {
+                    self.quote = self.number.clone();
+                    self.quote_changed = true;
+                    sender.output(BillingOutput::Quote(Some(self.quote.clone()))).unwrap();
+                }
                 self.bill_type = bill_type.clone();
                 sender.output(BillingOutput::Type(bill_type)).unwrap();
             }
@@ -206,7 +679,163 @@ impl SimpleComponent for BillingModel {
                 self.number = number;
                 sender.output(BillingOutput::Number(self.number.clone())).unwrap();
             }
+            BillingInput::Quote(quote) => {
+                self.quote = quote.trim().to_string();
+                let quote = (!self.quote.is_empty()).then(|| self.quote.clone());
+                sender.output(BillingOutput::Quote(quote)).unwrap();
+            }
+            BillingInput::Nature(nature) => {
+                self.nature = nature;
+                sender.output(BillingOutput::Nature(self.nature.clone())).unwrap();
+            }
+            BillingInput::ApplyDefaults(defaults) => {
+                self.applying_defaults = true;
+                if let Some(nature) = defaults.nature {
+                    self.nature = nature;
+                    sender.output(BillingOutput::Nature(self.nature.clone())).unwrap();
+                }
+                if let Some(diffuseur) = defaults.diffuseur {
+                    self.diffuseur = diffuseur;
+                    sender.output(BillingOutput::Diffuseur(self.diffuseur)).unwrap();
+                }
+                if let Some(payment_terms) = defaults.payment_terms {
+                    self.payment_terms = payment_terms;
+                    if let PaymentTerms::Days(days) = payment_terms {
+                        self.payment_days = days;
+                    }
+                    self.due_date_row.emit(DateRowInput::Set(self.payment_terms.due_date(self.date)));
+                    sender.output(BillingOutput::PaymentTerms(self.payment_terms)).unwrap();
+                }
+            }
             BillingInput::Dispense(filename) => self.dispense_file_name = filename,
+            BillingInput::DispenseWarning(warning) => self.dispense_warning = warning,
+            BillingInput::Contributions(contributions) => self.contributions = contributions,
+            BillingInput::Withholding(withholding) => self.withholding = withholding,
+            BillingInput::AddAttachment(attachment) => {
+                self.attachments.guard().push_back(attachment);
+                self.send_attachments(&sender);
+            }
+            BillingInput::PreviewAttachment(index) => {
+                if let Some(row) = self.attachments.get(index.current_index()) {
+                    sender.output(BillingOutput::PreviewAttachment(row.attachment.path.clone())).unwrap();
+                }
+            }
+            BillingInput::MoveAttachmentUp(index) => {
+                let index = index.current_index();
+                if index > 0 {
+                    self.attachments.guard().move_to(index, index - 1);
+                    self.send_attachments(&sender);
+                }
+            }
+            BillingInput::DeleteAttachment(index) => {
+                self.attachments.guard().remove(index.current_index());
+                self.send_attachments(&sender);
+            }
+            BillingInput::HasRightsTransfer(value) => {
+                self.has_rights_transfer = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::RightsMedia(value) => {
+                self.rights_transfer.media = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::RightsTerritory(value) => {
+                self.rights_transfer.territory = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::RightsDuration(value) => {
+                self.rights_transfer.duration = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::RightsExclusive(value) => {
+                self.rights_transfer.exclusive = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::RightsShare(value) => {
+                self.rights_transfer.rights_share = value;
+                self.send_rights_transfer(&sender);
+            }
+            BillingInput::Dispensed(value) => {
+                self.dispensed = value;
+                sender.output(BillingOutput::Dispensed(self.dispensed)).unwrap();
+            }
+            BillingInput::Date(date) => {
+                self.date = date;
+                sender.output(BillingOutput::Date(date)).unwrap();
+                self.due_date_row.emit(DateRowInput::Set(self.payment_terms.due_date(date)));
+            }
+            BillingInput::PaymentTermsKind(index) => {
+                let date = self.date;
+                self.payment_terms = match index {
+                    0 => PaymentTerms::Days(self.payment_days),
+                    1 => PaymentTerms::EndOfMonth,
+                    2 => PaymentTerms::OnReceipt,
+                    _ => PaymentTerms::Custom(self.payment_terms.due_date(date)),
+                };
+                self.due_date_row.emit(DateRowInput::Set(self.payment_terms.due_date(date)));
+                sender.output(BillingOutput::PaymentTerms(self.payment_terms)).unwrap();
+            }
+            BillingInput::PaymentDays(days) => {
+                self.payment_days = days;
+                self.payment_terms = PaymentTerms::Days(days);
+                self.due_date_row.emit(DateRowInput::Set(self.payment_terms.due_date(self.date)));
+                sender.output(BillingOutput::PaymentTerms(self.payment_terms)).unwrap();
+            }
+            BillingInput::DueDate(due_date) => {
+                self.payment_terms = PaymentTerms::Custom(due_date);
+                sender.output(BillingOutput::PaymentTerms(self.payment_terms)).unwrap();
+            }
+            BillingInput::DevisValidity(days) => {
+                self.devis_validity = days;
+                sender.output(BillingOutput::DevisValidity(days)).unwrap();
+            }
+            BillingInput::HasServiceDate(value) => {
+                self.has_service_date = value;
+                self.send_service_date(&sender);
+            }
+            BillingInput::HasServiceEnd(value) => {
+                self.has_service_end = value;
+                self.service_date.end = if value {
+                    Some(self.service_end_row.model().date())
+                } else {
+                    None
+                };
+                self.send_service_date(&sender);
+            }
+            BillingInput::ServiceStart(date) => {
+                self.service_date.start = date;
+                self.send_service_date(&sender);
+            }
+            BillingInput::ServiceEnd(date) => {
+                self.service_date.end = Some(date);
+                self.send_service_date(&sender);
+            }
         }
+        self.update_due_date_row();
+        self.service_end_row.widget().set_sensitive(self.has_service_end);
+        self.service_end_row.widget().set_class_active("error", self.has_service_end && !self.service_date.valid());
+    }
+}
+
+impl BillingModel {
+    fn send_rights_transfer(&self, sender: &ComponentSender<Self>) {
+        let rights_transfer = if self.has_rights_transfer { Some(self.rights_transfer.clone()) } else { None };
+        sender.output(BillingOutput::RightsTransfer(rights_transfer)).unwrap();
+    }
+
+    fn send_attachments(&self, sender: &ComponentSender<Self>) {
+        let attachments = self.attachments.iter().map(|row| row.attachment.clone()).collect();
+        sender.output(BillingOutput::Attachments(attachments)).unwrap();
+    }
+
+    fn send_service_date(&self, sender: &ComponentSender<Self>) {
+        let service_date = if self.has_service_date { Some(self.service_date) } else { None };
+        sender.output(BillingOutput::ServiceDate(service_date)).unwrap();
+    }
+
+    fn update_due_date_row(&self) {
+        // only a custom due date can be picked, other terms compute it
+        self.due_date_row.widget().set_visible(matches!(self.bill_type, BillType::Facture));
+        self.due_date_row.widget().set_sensitive(matches!(self.payment_terms, PaymentTerms::Custom(_)));
     }
 }
@@ -0,0 +1,114 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::app::{Attachment, AttachmentKind};
+
+pub struct Model {
+    pub attachment: Attachment,
+}
+
+#[derive(Debug)]
+pub enum AttachmentOutput {
+    Preview(DynamicIndex),
+    MoveUp(DynamicIndex),
+    Delete(DynamicIndex),
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = ();
+    type Output = AttachmentOutput;
+    type Init = Attachment;
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ActionRow {
+            set_title: &self.attachment.name,
+            set_subtitle: match self.attachment.kind {
+                AttachmentKind::Pdf => "pdf",
+                AttachmentKind::Image => "image",
+            },
+
+            set_focusable: false,
+
+            add_suffix = &gtk::Button {
+                set_tooltip: "Aperçu",
+                set_icon_name: "view-reveal-symbolic",
+                set_focus_on_click: false,
+                set_has_frame: false,
+                set_has_tooltip: true,
+                // poppler can only preview pdfs, images are visible in the invoice
+                set_visible: self.attachment.kind == AttachmentKind::Pdf,
+
+                add_css_class: "circular",
+                set_margin_top: 8,
+                set_margin_bottom: 8,
+
+                connect_clicked[sender, index] => move |_| {
+                    sender.output(AttachmentOutput::Preview(index.clone())).unwrap()
+                }
+            },
+
+            add_suffix = &gtk::Button {
+                set_tooltip: "Monter",
+                set_icon_name: "go-up-symbolic",
+                set_focus_on_click: false,
+                set_has_frame: false,
+                set_has_tooltip: true,
+
+                add_css_class: "circular",
+                set_margin_top: 8,
+                set_margin_bottom: 8,
+
+                connect_clicked[sender, index] => move |_| {
+                    sender.output(AttachmentOutput::MoveUp(index.clone())).unwrap()
+                }
+            },
+
+            add_suffix = &gtk::Separator {
+                set_orientation: gtk::Orientation::Vertical,
+                set_margin_end: 5,
+                set_margin_start: 5,
+            },
+
+            add_suffix = &gtk::Button {
+                set_tooltip: "Supprimer",
+                set_icon_name: "user-trash-symbolic",
+                add_css_class: "destructive-action",
+                set_focus_on_click: false,
+                set_has_frame: false,
+                set_has_tooltip: true,
+
+                add_css_class: "circular",
+                set_margin_top: 8,
+                set_margin_bottom: 8,
+
+                connect_clicked[sender, index] => move |_| {
+                    sender.output(AttachmentOutput::Delete(index.clone())).unwrap()
+                }
+            },
+        }
+    }
+
+    fn init_model(
+        attachment: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { attachment }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+}
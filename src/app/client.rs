@@ -1,12 +1,20 @@
 use std::fmt;
 
-use crate::app::Address;
+use uuid::Uuid;
+
+use crate::app::{Address, PaymentTerms, Product};
+use crate::validation;
 
 pub type ClientName = String;
+/// stable identifier, the name can be edited
+pub type ClientId = Uuid;
 
 #[derive(Debug,Clone,Default)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Client {
+    /// nil for clients stored before ids existed, see `Config::load_with_check`
+    #[serde(default)]
+    pub id: ClientId,
     pub name: ClientName,
     pub address: Address,
     pub siret: Option<String>,
@@ -14,18 +22,86 @@ pub struct Client {
     pub tva: Option<String>,
     pub tva_icc: Option<String>,
     pub custom_field: Option<String>,
+    /// contact person
+    pub contact: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    /// delivery or diffuseur address, when it differs from the billing address
+    pub delivery: Option<DeliveryAddress>,
+    /// deleted client still referenced by archived invoices, hidden from the selector
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub defaults: ClientDefaults,
 }
 impl Client {
+    /// empty client with a new id
+    pub(crate) fn new() -> Client {
+        Client { id: Uuid::new_v4(), ..Client::default() }
+    }
+
     pub(crate) fn valid(&self) -> bool {
         if self.name.is_empty() { return false; }
         if !self.address.valid() { return false; }
+        if self.delivery.as_ref().is_some_and(|delivery| !delivery.valid()) { return false; }
+        if self.siret_error().is_some() { return false; }
+        if self.ape_error().is_some() { return false; }
+        if self.tva_error().is_some() { return false; }
+        if self.tva_icc_error().is_some() { return false; }
         true
     }
+
+    // optional identifiers are only checked when filled
+
+    pub(crate) fn siret_error(&self) -> Option<String> {
+        validation::siret(self.siret.as_ref()?).err()
+    }
+
+    pub(crate) fn ape_error(&self) -> Option<String> {
+        validation::ape(self.code_ape.as_ref()?).err()
+    }
+
+    pub(crate) fn tva_error(&self) -> Option<String> {
+        validation::vat(self.tva.as_ref()?, self.siret.as_deref()).err()
+    }
+
+    pub(crate) fn tva_icc_error(&self) -> Option<String> {
+        validation::vat(self.tva_icc.as_ref()?, self.siret.as_deref()).err()
+    }
+}
+
+/// invoice values applied when the client is picked, they can still be edited on the invoice
+#[derive(Debug,Clone,Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ClientDefaults {
+    pub nature: Option<String>,
+    pub diffuseur: Option<bool>,
+    pub payment_terms: Option<PaymentTerms>,
+    /// path of a latex template replacing the bundled one
+    pub template: Option<String>,
+    /// product lines billed on every invoice
+    pub products: Vec<Product>,
+}
+
+#[derive(Debug,Clone,Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct DeliveryAddress {
+    pub name: String,
+    pub address: Address,
+}
+impl DeliveryAddress {
+    pub(crate) fn valid(&self) -> bool {
+        !self.name.is_empty() && self.address.valid()
+    }
 }
 
 impl fmt::Display for Client {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // Customize so only `x` and `y` are denoted.
-        write!(f, "{}", self.name)
+        if self.archived {
+            write!(f, "{} (archivé)", self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
     }
 }
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use gtk::gio::Cancellable;
+use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
+
+use crate::app::Client;
+use crate::contacts;
+
+mod selection_row;
+
+
+/// export of all or some of the clients to a vcard or csv file
+pub(crate) struct ClientExportModel {
+    rows: FactoryVecDeque<selection_row::Model>,
+    format: ExportFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    VCard,
+    Csv,
+}
+
+impl ExportFormat {
+    /// in the same order as the format combo row
+    const ALL: [ExportFormat; 2] = [ExportFormat::VCard, ExportFormat::Csv];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::VCard => "vCard (Contacts GNOME)",
+            ExportFormat::Csv => "CSV (tableur)",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::VCard => "vcf",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum ClientExportInput {
+    Open(Vec<Client>),
+    SelectAll(bool),
+    /// index in `ExportFormat::ALL`
+    Format(u32),
+    SelectionChanged,
+    Export,
+    Save(PathBuf),
+}
+
+#[derive(Debug)]
+pub(crate) enum ClientExportOutput {
+    Exported,
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for ClientExportModel {
+    type Init = ();
+    type Input = ClientExportInput;
+    type Output = ClientExportOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: "Exporter des clients",
+            set_content_width: 600,
+            set_content_height: 700,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    pack_end = &gtk::Button {
+                        set_label: "Exporter",
+                        add_css_class: "suggested-action",
+                        #[watch] set_sensitive: model.rows.iter().any(|row| row.selected),
+                        connect_clicked => ClientExportInput::Export,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &adw::PreferencesPage {
+                    add = &adw::PreferencesGroup {
+                        add = &adw::ComboRow {
+                            set_title: "Format",
+                            set_model: Some(&gtk::StringList::new(
+                                &ExportFormat::ALL.map(|format| format.label())
+                            )),
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(ClientExportInput::Format(row.selected()));
+                            },
+                        },
+                    },
+
+                    #[local_ref]
+                    add = rows_box -> adw::PreferencesGroup {
+                        #[watch] set_title: &format!("{} clients sélectionnés",
+                            model.rows.iter().filter(|row| row.selected).count()),
+
+                        #[wrap(Some)]
+                        set_header_suffix = &gtk::Box {
+                            append = &gtk::Button {
+                                set_label: "Tout",
+                                add_css_class: "flat",
+                                connect_clicked => ClientExportInput::SelectAll(true),
+                            },
+                            append = &gtk::Button {
+                                set_label: "Aucun",
+                                add_css_class: "flat",
+                                connect_clicked => ClientExportInput::SelectAll(false),
+                            },
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let rows = FactoryVecDeque::builder()
+            .launch(adw::PreferencesGroup::default())
+            .forward(sender.input_sender(), |_| ClientExportInput::SelectionChanged);
+
+        let model = ClientExportModel {
+            rows,
+            format: ExportFormat::VCard,
+        };
+
+        let rows_box = model.rows.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            ClientExportInput::Open(mut clients) => {
+                clients.sort_by(|a, b| a.name.cmp(&b.name));
+                let mut rows = self.rows.guard();
+                rows.clear();
+                for client in clients {
+                    rows.push_back(client);
+                }
+            },
+            ClientExportInput::SelectAll(selected) => {
+                for row in self.rows.guard().iter_mut() {
+                    row.selected = selected;
+                }
+            },
+            ClientExportInput::Format(index) => {
+                self.format = ExportFormat::ALL[index as usize];
+            },
+            ClientExportInput::SelectionChanged => (),
+            ClientExportInput::Export => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Exporter des clients")
+                    .modal(true)
+                    .initial_name(format!("clients.{}", self.format.extension()))
+                    .build();
+
+                let window = self.rows.widget().root().and_downcast::<gtk::Window>();
+                let sender = sender.clone();
+                dialog.save(window.as_ref(),
+                    Some(&Cancellable::new()),
+                    move |file| {
+                        if let Some(path) = file.ok().and_then(|file| file.path()) {
+                            sender.input(ClientExportInput::Save(path));
+                        }
+                    },
+                );
+            },
+            ClientExportInput::Save(path) => {
+                let clients: Vec<Client> = self.rows.iter()
+                    .filter(|row| row.selected)
+                    .map(|row| row.client.clone())
+                    .collect();
+                let written = match self.format {
+                    ExportFormat::VCard => contacts::vcard::write_file(&path, &clients),
+                    ExportFormat::Csv => contacts::csv::write_file(&path, &clients),
+                };
+                match written {
+                    Ok(()) => sender.output(ClientExportOutput::Exported).unwrap(),
+                    Err(e) => println!("Couldn't export clients to {}: {e}", path.display()),
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::app::Client;
+
+pub struct Model {
+    pub client: Client,
+    pub selected: bool,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = bool;
+    /// the selection changed
+    type Output = ();
+    type Init = Client;
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ActionRow {
+            set_title: &self.client.to_string(),
+            set_subtitle: &format!("{} {}", self.client.address.postcode, self.client.address.city),
+            set_activatable_widget: Some(&check),
+
+            #[name(check)]
+            add_prefix = &gtk::CheckButton {
+                #[watch]
+                #[block_signal(toggled_handler)]
+                set_active: self.selected,
+
+                connect_toggled[sender] => move |check| {
+                    sender.input(check.is_active());
+                } @toggled_handler,
+            },
+        }
+    }
+
+    fn init_model(
+        client: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { client, selected: true }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn update(&mut self, selected: Self::Input, sender: FactorySender<Self>) {
+        self.selected = selected;
+        sender.output(()).unwrap();
+    }
+}
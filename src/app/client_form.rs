@@ -1,7 +1,8 @@
 use adw::prelude::*;
 use relm4::prelude::*;
 
-use crate::app::Client;
+use crate::app::{Client, DeliveryAddress, PaymentTerms, Product};
+use crate::app::billing_view::DEFAULT_PAYMENT_DAYS;
 
 
 #[derive(Clone)]
@@ -16,16 +17,45 @@ enum Field {
     NumberAndStreet(String),
     Postcode(String),
     City(String),
+    Country(String),
+    Contact(String),
+    Email(String),
+    Phone(String),
+    HasDelivery(bool),
+    DeliveryName(String),
+    DeliveryNumberAndStreet(String),
+    DeliveryPostcode(String),
+    DeliveryCity(String),
+    DeliveryCountry(String),
     Siret(String),
     Ape(String),
     Tva(String),
     TvaIcc(String),
     Custom(String),
+    DefaultNature(String),
+    /// index in `DIFFUSEUR_LABELS`
+    DefaultDiffuseur(u32),
+    /// index in `PAYMENT_TERMS_LABELS`
+    DefaultPaymentTerms(u32),
+    DefaultPaymentDays(u32),
+    DefaultTemplate(String),
+    UseCurrentProducts,
+    ClearProducts,
 }
 
+const DIFFUSEUR_LABELS: [&str; 3] = ["Non précisé", "Oui", "Non"];
+/// custom due dates only make sense for a given invoice
+const PAYMENT_TERMS_LABELS: [&str; 4] = ["Non précisé", "Nombre de jours", "Fin de mois", "À réception"];
+
 #[derive(Clone)]
 pub(crate) struct ClientFormModel {
     client: Client,
+    /// kept when the delivery address is disabled, to restore it
+    delivery: DeliveryAddress,
+    /// last number of days entered for the default payment terms
+    payment_days: u32,
+    /// products of the invoice being edited, to be saved as recurring products
+    current_products: Vec<Product>,
     mode: Mode,
     is_valid: bool,
     has_been_edited: bool,
@@ -45,6 +75,7 @@ pub(crate) enum ClientFormInput {
     Create,
     Edited(Field), // FIXME: should be private
     Validated, // FIXME: should be private
+    CurrentProducts(Vec<Product>),
 }
 
 #[relm4::component(pub(crate))]
@@ -144,12 +175,150 @@ impl SimpleComponent for ClientFormModel {
                             } @city_handler,
                         },
 
-                        // add = &adw::EntryRow {
-                        //     set_title: "Country",
-                        //     connect_changed[sender] => move |entry_row| {
-                        //         sender.input(ClientFormInput::CountryEdited(entry_row.property("text")));
-                        //     }
-                        // },
+                        #[name(country)]
+                        add = &adw::EntryRow {
+                            set_title: "Pays",
+
+                            #[track(model.initializing)]
+                            #[block_signal(country_handler)]
+                            set_text: &model.client.address.country,
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::Country(entry_row.property("text"))));
+                            } @country_handler,
+                        },
+                    },
+
+                    add = &adw::PreferencesGroup {
+                        set_title: "Contact",
+
+                        #[name(contact)]
+                        add = &adw::EntryRow {
+                            set_title: "Interlocuteur",
+                            #[track(model.initializing)]
+                            #[block_signal(contact_handler)]
+                            set_text: if let Some(contact) = &model.client.contact { contact } else { "" },
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::Contact(entry_row.property("text"))));
+                            } @contact_handler,
+                        },
+
+                        #[name(email)]
+                        add = &adw::EntryRow {
+                            set_title: "Mail",
+                            set_input_purpose: gtk::InputPurpose::Email,
+                            #[track(model.initializing)]
+                            #[block_signal(email_handler)]
+                            set_text: if let Some(email) = &model.client.email { email } else { "" },
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::Email(entry_row.property("text"))));
+                            } @email_handler,
+                        },
+
+                        #[name(phone)]
+                        add = &adw::EntryRow {
+                            set_title: "Téléphone",
+                            set_input_purpose: gtk::InputPurpose::Phone,
+                            #[track(model.initializing)]
+                            #[block_signal(phone_handler)]
+                            set_text: if let Some(phone) = &model.client.phone { phone } else { "" },
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::Phone(entry_row.property("text"))));
+                            } @phone_handler,
+                        },
+                    },
+
+                    add = &adw::PreferencesGroup {
+                        #[name(delivery)]
+                        add = &adw::ExpanderRow {
+                            set_title: "Adresse de livraison / diffuseur",
+                            set_subtitle: "si différente de l'adresse de facturation",
+                            set_show_enable_switch: true,
+
+                            #[track(model.initializing)]
+                            #[block_signal(delivery_handler)]
+                            set_enable_expansion: model.client.delivery.is_some(),
+
+                            connect_enable_expansion_notify[sender] => move |row| {
+                                sender.input(ClientFormInput::Edited(Field::HasDelivery(row.enables_expansion())));
+                            } @delivery_handler,
+
+                            #[name(delivery_name)]
+                            add_row = &adw::EntryRow {
+                                set_title: "Nom *",
+
+                                #[track(model.initializing)]
+                                #[block_signal(delivery_name_handler)]
+                                set_text: &model.delivery.name,
+
+                                #[watch] set_css_classes: if model.delivery.name.is_empty() { &["error"] } else { &[""] },
+
+                                connect_changed[sender] => move |entry_row| {
+                                    sender.input(ClientFormInput::Edited(Field::DeliveryName(entry_row.property("text"))));
+                                } @delivery_name_handler,
+                            },
+
+                            #[name(delivery_street)]
+                            add_row = &adw::EntryRow {
+                                set_title: "Addresse *",
+
+                                #[track(model.initializing)]
+                                #[block_signal(delivery_street_handler)]
+                                set_text: &model.delivery.address.number_and_street,
+
+                                #[watch] set_css_classes: if model.delivery.address.number_and_street.is_empty() { &["error"] } else { &[""] },
+
+                                connect_changed[sender] => move |entry_row| {
+                                    sender.input(ClientFormInput::Edited(Field::DeliveryNumberAndStreet(entry_row.property("text"))));
+                                } @delivery_street_handler,
+                            },
+
+                            #[name(delivery_postcode)]
+                            add_row = &adw::EntryRow {
+                                set_title: "Code postal *",
+
+                                #[track(model.initializing)]
+                                #[block_signal(delivery_postcode_handler)]
+                                set_text: &model.delivery.address.postcode,
+
+                                #[watch] set_css_classes: if model.delivery.address.postcode.is_empty() { &["error"] } else { &[""] },
+
+                                connect_changed[sender] => move |entry_row| {
+                                    sender.input(ClientFormInput::Edited(Field::DeliveryPostcode(entry_row.property("text"))));
+                                } @delivery_postcode_handler,
+                            },
+
+                            #[name(delivery_city)]
+                            add_row = &adw::EntryRow {
+                                set_title: "Ville *",
+
+                                #[track(model.initializing)]
+                                #[block_signal(delivery_city_handler)]
+                                set_text: &model.delivery.address.city,
+
+                                #[watch] set_css_classes: if model.delivery.address.city.is_empty() { &["error"] } else { &[""] },
+
+                                connect_changed[sender] => move |entry_row| {
+                                    sender.input(ClientFormInput::Edited(Field::DeliveryCity(entry_row.property("text"))));
+                                } @delivery_city_handler,
+                            },
+
+                            #[name(delivery_country)]
+                            add_row = &adw::EntryRow {
+                                set_title: "Pays",
+
+                                #[track(model.initializing)]
+                                #[block_signal(delivery_country_handler)]
+                                set_text: &model.delivery.address.country,
+
+                                connect_changed[sender] => move |entry_row| {
+                                    sender.input(ClientFormInput::Edited(Field::DeliveryCountry(entry_row.property("text"))));
+                                } @delivery_country_handler,
+                            },
+                        },
                     },
 
                     add = &adw::PreferencesGroup {
@@ -160,6 +329,14 @@ impl SimpleComponent for ClientFormModel {
                             #[block_signal(siret_handler)]
                             set_text: if let Some(siret) = &model.client.siret { siret } else { "" },
 
+                            #[watch] set_css_classes: if model.client.siret_error().is_some() { &["error"] } else { &[""] },
+
+                            add_suffix = &gtk::Label {
+                                add_css_class: "error",
+                                add_css_class: "caption",
+                                #[watch] set_label: &model.client.siret_error().unwrap_or_default(),
+                            },
+
                             connect_changed[sender] => move |entry_row| {
                                 sender.input(ClientFormInput::Edited(Field::Siret(entry_row.property("text"))));
                             } @siret_handler,
@@ -172,6 +349,14 @@ impl SimpleComponent for ClientFormModel {
                             #[block_signal(code_ape_handler)]
                             set_text: if let Some(code_ape) = &model.client.code_ape { code_ape } else { "" },
 
+                            #[watch] set_css_classes: if model.client.ape_error().is_some() { &["error"] } else { &[""] },
+
+                            add_suffix = &gtk::Label {
+                                add_css_class: "error",
+                                add_css_class: "caption",
+                                #[watch] set_label: &model.client.ape_error().unwrap_or_default(),
+                            },
+
                             connect_changed[sender] => move |entry_row| {
                                 sender.input(ClientFormInput::Edited(Field::Ape(entry_row.property("text"))));
                             } @code_ape_handler,
@@ -184,6 +369,14 @@ impl SimpleComponent for ClientFormModel {
                             #[block_signal(tva_handler)]
                             set_text: if let Some(tva) = &model.client.tva { tva } else { "" },
 
+                            #[watch] set_css_classes: if model.client.tva_error().is_some() { &["error"] } else { &[""] },
+
+                            add_suffix = &gtk::Label {
+                                add_css_class: "error",
+                                add_css_class: "caption",
+                                #[watch] set_label: &model.client.tva_error().unwrap_or_default(),
+                            },
+
                             connect_changed[sender] => move |entry_row| {
                                 sender.input(ClientFormInput::Edited(Field::Tva(entry_row.property("text"))));
                             } @tva_handler,
@@ -196,6 +389,14 @@ impl SimpleComponent for ClientFormModel {
                             #[block_signal(tva_icc_handler)]
                             set_text: if let Some(tva_icc) = &model.client.tva_icc { tva_icc } else { "" },
 
+                            #[watch] set_css_classes: if model.client.tva_icc_error().is_some() { &["error"] } else { &[""] },
+
+                            add_suffix = &gtk::Label {
+                                add_css_class: "error",
+                                add_css_class: "caption",
+                                #[watch] set_label: &model.client.tva_icc_error().unwrap_or_default(),
+                            },
+
                             connect_changed[sender] => move |entry_row| {
                                 sender.input(ClientFormInput::Edited(Field::TvaIcc(entry_row.property("text"))));
                             } @tva_icc_handler,
@@ -223,6 +424,112 @@ impl SimpleComponent for ClientFormModel {
                         },
                     },
 
+                    add = &adw::PreferencesGroup {
+                        set_title: "Valeurs par défaut",
+                        set_description: Some("appliquées aux factures à la sélection du client"),
+
+                        #[name(default_nature)]
+                        add = &adw::EntryRow {
+                            set_title: "Nature",
+                            #[track(model.initializing)]
+                            #[block_signal(default_nature_handler)]
+                            set_text: if let Some(nature) = &model.client.defaults.nature { nature } else { "" },
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::DefaultNature(entry_row.property("text"))));
+                            } @default_nature_handler,
+                        },
+
+                        #[name(default_diffuseur)]
+                        add = &adw::ComboRow {
+                            set_title: "Contributions diffuseur",
+                            set_model: Some(&gtk::StringList::new(&DIFFUSEUR_LABELS)),
+                            #[track(model.initializing)]
+                            #[block_signal(default_diffuseur_handler)]
+                            set_selected: match model.client.defaults.diffuseur {
+                                None => 0,
+                                Some(true) => 1,
+                                Some(false) => 2,
+                            },
+
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(ClientFormInput::Edited(Field::DefaultDiffuseur(row.selected())));
+                            } @default_diffuseur_handler,
+                        },
+
+                        #[name(default_payment_terms)]
+                        add = &adw::ComboRow {
+                            set_title: "Échéance",
+                            set_model: Some(&gtk::StringList::new(&PAYMENT_TERMS_LABELS)),
+                            #[track(model.initializing)]
+                            #[block_signal(default_payment_terms_handler)]
+                            set_selected: match model.client.defaults.payment_terms {
+                                None | Some(PaymentTerms::Custom(_)) => 0,
+                                Some(terms) => terms.index() + 1,
+                            },
+
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(ClientFormInput::Edited(Field::DefaultPaymentTerms(row.selected())));
+                            } @default_payment_terms_handler,
+                        },
+
+                        #[name(default_payment_days)]
+                        add = &adw::SpinRow {
+                            set_title: "Délai de paiement (jours)",
+                            set_adjustment: Some(&gtk::Adjustment::new(DEFAULT_PAYMENT_DAYS.into(), 0.0, 365.0, 1.0, 10.0, 0.0)),
+                            #[watch] set_visible: matches!(model.client.defaults.payment_terms, Some(PaymentTerms::Days(_))),
+                            #[track(model.initializing)]
+                            #[block_signal(default_payment_days_handler)]
+                            set_value: model.payment_days.into(),
+
+                            connect_changed[sender] => move |row| {
+                                sender.input(ClientFormInput::Edited(Field::DefaultPaymentDays(row.value() as u32)));
+                            } @default_payment_days_handler,
+                        },
+
+                        #[name(default_template)]
+                        add = &adw::EntryRow {
+                            set_title: "Modèle LaTeX (chemin)",
+                            #[track(model.initializing)]
+                            #[block_signal(default_template_handler)]
+                            set_text: if let Some(template) = &model.client.defaults.template { template } else { "" },
+
+                            connect_changed[sender] => move |entry_row| {
+                                sender.input(ClientFormInput::Edited(Field::DefaultTemplate(entry_row.property("text"))));
+                            } @default_template_handler,
+                        },
+
+                        add = &adw::ActionRow {
+                            set_title: "Lignes récurrentes",
+                            #[watch] set_subtitle: &if model.client.defaults.products.is_empty() {
+                                "aucune".to_string()
+                            } else {
+                                model.client.defaults.products.iter()
+                                    .map(|product| product.name.clone())
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            },
+
+                            add_suffix = &gtk::Button {
+                                set_tooltip: "Reprendre les lignes de la facture en cours",
+                                set_icon_name: "document-save-symbolic",
+                                set_valign: gtk::Align::Center,
+                                add_css_class: "flat",
+                                #[watch] set_sensitive: !model.current_products.is_empty(),
+                                connect_clicked => ClientFormInput::Edited(Field::UseCurrentProducts),
+                            },
+
+                            add_suffix = &gtk::Button {
+                                set_tooltip: "Supprimer les lignes récurrentes",
+                                set_icon_name: "user-trash-symbolic",
+                                set_valign: gtk::Align::Center,
+                                add_css_class: "flat",
+                                #[watch] set_sensitive: !model.client.defaults.products.is_empty(),
+                                connect_clicked => ClientFormInput::Edited(Field::ClearProducts),
+                            },
+                        },
+                    },
+
                     add = &adw::PreferencesGroup {
                         add = &adw::ButtonRow {
                             #[watch] set_activatable: model.is_valid,
@@ -255,6 +562,9 @@ impl SimpleComponent for ClientFormModel {
 
         let model = ClientFormModel {
             client: Client::default(),
+            delivery: DeliveryAddress::default(),
+            payment_days: DEFAULT_PAYMENT_DAYS,
+            current_products: Vec::new(),
             mode: Mode::Creating,
             is_valid: false,
             has_been_edited: false,
@@ -270,13 +580,20 @@ impl SimpleComponent for ClientFormModel {
             ClientFormInput::Edit(client) => {
                 self.initializing = true;
                 self.has_been_edited = false;
+                self.delivery = client.delivery.clone().unwrap_or_default();
+                self.payment_days = match client.defaults.payment_terms {
+                    Some(PaymentTerms::Days(days)) => days,
+                    _ => DEFAULT_PAYMENT_DAYS,
+                };
                 self.client = client;
                 self.mode = Mode::Editing;
             },
             ClientFormInput::Create => {
                 self.initializing = true;
                 self.has_been_edited = false;
-                self.client =  Client::default();
+                self.client = Client::new();
+                self.delivery = DeliveryAddress::default();
+                self.payment_days = DEFAULT_PAYMENT_DAYS;
                 self.mode = Mode::Creating;
             },
             ClientFormInput::Edited(field) => {
@@ -287,14 +604,49 @@ impl SimpleComponent for ClientFormModel {
                     Field::NumberAndStreet(value) => self.client.address.number_and_street = value,
                     Field::Postcode(value) => self.client.address.postcode = value,
                     Field::City(value) => self.client.address.city = value,
+                    Field::Country(value) => self.client.address.country = value,
+                    Field::Contact(value) => self.client.contact = if value.is_empty() { None } else { Some(value) },
+                    Field::Email(value) => self.client.email = if value.is_empty() { None } else { Some(value) },
+                    Field::Phone(value) => self.client.phone = if value.is_empty() { None } else { Some(value) },
+                    Field::HasDelivery(value) => {
+                        self.client.delivery = if value { Some(self.delivery.clone()) } else { None }
+                    },
+                    Field::DeliveryName(value) => self.delivery.name = value,
+                    Field::DeliveryNumberAndStreet(value) => self.delivery.address.number_and_street = value,
+                    Field::DeliveryPostcode(value) => self.delivery.address.postcode = value,
+                    Field::DeliveryCity(value) => self.delivery.address.city = value,
+                    Field::DeliveryCountry(value) => self.delivery.address.country = value,
                     Field::Siret(value) => self.client.siret = if value.is_empty() { None } else { Some(value) },
                     Field::Ape(value) => self.client.code_ape = if value.is_empty() { None } else { Some(value) },
                     Field::Tva(value) => self.client.tva = if value.is_empty() { None } else { Some(value) },
                     Field::TvaIcc(value) => self.client.tva_icc = if value.is_empty() { None } else { Some(value) },
                     Field::Custom(value) => self.client.custom_field = if value.is_empty() { None } else { Some(value) },
+                    Field::DefaultNature(value) => self.client.defaults.nature = if value.is_empty() { None } else { Some(value) },
+                    Field::DefaultDiffuseur(index) => self.client.defaults.diffuseur = match index {
+                        1 => Some(true),
+                        2 => Some(false),
+                        _ => None,
+                    },
+                    Field::DefaultPaymentTerms(index) => self.client.defaults.payment_terms = match index {
+                        1 => Some(PaymentTerms::Days(self.payment_days)),
+                        2 => Some(PaymentTerms::EndOfMonth),
+                        3 => Some(PaymentTerms::OnReceipt),
+                        _ => None,
+                    },
+                    Field::DefaultPaymentDays(days) => {
+                        self.payment_days = days;
+                        self.client.defaults.payment_terms = Some(PaymentTerms::Days(days));
+                    },
+                    Field::DefaultTemplate(value) => self.client.defaults.template = if value.is_empty() { None } else { Some(value) },
+                    Field::UseCurrentProducts => self.client.defaults.products = self.current_products.clone(),
+                    Field::ClearProducts => self.client.defaults.products.clear(),
                 };
+                if self.client.delivery.is_some() {
+                    self.client.delivery = Some(self.delivery.clone());
+                }
                 self.is_valid = self.client.valid();
             },
+            ClientFormInput::CurrentProducts(products) => self.current_products = products,
             ClientFormInput::Validated => {
                 match self.mode {
                     Mode::Creating => sender.output(ClientFormOutput::Created(self.client.clone())).unwrap(),
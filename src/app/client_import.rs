@@ -0,0 +1,207 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
+
+use crate::app::Client;
+use crate::contacts::{self, csv::Table, ClientField};
+
+mod mapping_row;
+mod record_row;
+use record_row::ImportAction;
+
+
+/// preview of the clients read from a file, with the columns mapping of csv files
+pub(crate) struct ClientImportModel {
+    source: ImportSource,
+    mapping: Vec<ClientField>,
+    /// clients already listed, to detect duplicates
+    existing: Vec<Client>,
+    /// the file couldn't be read
+    error: Option<String>,
+    mapping_rows: FactoryVecDeque<mapping_row::Model>,
+    records: FactoryVecDeque<record_row::Model>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ImportSource {
+    Table(Table),
+    Clients(Vec<Client>),
+}
+
+#[derive(Debug)]
+pub(crate) enum ClientImportInput {
+    Open { source: ImportSource, existing: Vec<Client> },
+    /// the file couldn't be read, shown instead of the clients
+    ReadFailed(String),
+    MappingChanged(DynamicIndex, ClientField),
+    /// an import action was changed, refreshes the import button
+    ActionChanged,
+    Import,
+}
+
+#[derive(Debug)]
+pub(crate) enum ClientImportOutput {
+    /// new clients, and existing clients completed with the imported data
+    Imported(Vec<Client>),
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for ClientImportModel {
+    type Init = ();
+    type Input = ClientImportInput;
+    type Output = ClientImportOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: "Importer des clients",
+            set_content_width: 600,
+            set_content_height: 700,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    pack_end = &gtk::Button {
+                        set_label: "Importer",
+                        add_css_class: "suggested-action",
+                        #[watch] set_sensitive: model.error.is_none()
+                            && model.records.iter().any(|record| record.action != ImportAction::Skip),
+                        connect_clicked => ClientImportInput::Import,
+                    },
+                },
+
+                #[wrap(Some)]
+                set_content = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+
+                    adw::StatusPage {
+                        set_vexpand: true,
+                        set_icon_name: Some("dialog-error-symbolic"),
+                        set_title: "Fichier illisible",
+                        #[watch] set_description: model.error.as_deref(),
+                        #[watch] set_visible: model.error.is_some(),
+                    },
+
+                    adw::PreferencesPage {
+                        set_vexpand: true,
+                        #[watch] set_visible: model.error.is_none(),
+
+                        #[local_ref]
+                        add = mapping_box -> adw::PreferencesGroup {
+                            set_title: "Colonnes",
+                            set_description: Some("champ du client lu dans chaque colonne"),
+                            #[watch] set_visible: matches!(model.source, ImportSource::Table(_)),
+                        },
+
+                        #[local_ref]
+                        add = records_box -> adw::PreferencesGroup {
+                            #[watch] set_title: &format!("{} clients", model.records.len()),
+                            set_description: Some("les doublons sont repérés par SIRET ou par nom, parmi les clients existants et dans le fichier"),
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mapping_rows = FactoryVecDeque::builder()
+            .launch(adw::PreferencesGroup::default())
+            .forward(sender.input_sender(), |output| match output {
+                mapping_row::MappingOutput::Changed(index, field) => ClientImportInput::MappingChanged(index, field),
+            });
+
+        let records = FactoryVecDeque::builder()
+            .launch(adw::PreferencesGroup::default())
+            .forward(sender.input_sender(), |_| ClientImportInput::ActionChanged);
+
+        let model = ClientImportModel {
+            source: ImportSource::Clients(Vec::new()),
+            mapping: Vec::new(),
+            existing: Vec::new(),
+            error: None,
+            mapping_rows,
+            records,
+        };
+
+        let mapping_box = model.mapping_rows.widget();
+        let records_box = model.records.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            ClientImportInput::Open { source, existing } => {
+                self.mapping = match &source {
+                    ImportSource::Table(table) => table.guess_mapping(),
+                    ImportSource::Clients(_) => Vec::new(),
+                };
+                if let ImportSource::Table(table) = &source {
+                    let mut mapping_rows = self.mapping_rows.guard();
+                    mapping_rows.clear();
+                    for (header, field) in table.headers.iter().zip(&self.mapping) {
+                        mapping_rows.push_back((header.clone(), *field));
+                    }
+                }
+                self.source = source;
+                self.existing = existing;
+                self.error = None;
+                // actions chosen for a previous file don't apply
+                self.records.guard().clear();
+                self.update_records();
+            },
+            ClientImportInput::ReadFailed(error) => {
+                self.error = Some(error);
+            },
+            ClientImportInput::MappingChanged(index, field) => {
+                self.mapping[index.current_index()] = field;
+                self.update_records();
+            },
+            ClientImportInput::ActionChanged => (),
+            ClientImportInput::Import => {
+                let mut clients: Vec<Client> = Vec::new();
+                for record in self.records.iter() {
+                    match (record.action, &record.duplicate) {
+                        (ImportAction::Add, _) => clients.push(record.client.clone()),
+                        (ImportAction::Merge, Some(duplicate)) => {
+                            // the duplicate may be an earlier record, or already completed by one
+                            match clients.iter_mut().find(|client| client.id == duplicate.id) {
+                                Some(client) => contacts::merge(client, &record.client),
+                                None => {
+                                    let mut client = duplicate.clone();
+                                    contacts::merge(&mut client, &record.client);
+                                    clients.push(client);
+                                },
+                            }
+                        },
+                        (ImportAction::Merge, None) | (ImportAction::Skip, _) => (),
+                    }
+                }
+                sender.output(ClientImportOutput::Imported(clients)).unwrap();
+            },
+        }
+    }
+}
+
+impl ClientImportModel {
+    fn update_records(&mut self) {
+        let clients = match &self.source {
+            ImportSource::Table(table) => table.clients(&self.mapping),
+            ImportSource::Clients(clients) => clients.clone(),
+        };
+        // actions are kept by row, the rows stay in the order of the file
+        let actions: Vec<ImportAction> = self.records.iter().map(|record| record.action).collect();
+        let mut records = self.records.guard();
+        records.clear();
+        for (index, client) in clients.iter().enumerate() {
+            let duplicate = contacts::find_duplicate(client, &self.existing)
+                .or_else(|| contacts::find_duplicate(client, &clients[..index]))
+                .cloned();
+            records.push_back((client.clone(), duplicate, actions.get(index).copied()));
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::contacts::ClientField;
+
+/// csv column and the client field it is read into
+pub struct Model {
+    header: String,
+    field: ClientField,
+}
+
+#[derive(Debug)]
+pub enum MappingOutput {
+    Changed(DynamicIndex, ClientField),
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = ();
+    type Output = MappingOutput;
+    type Init = (String, ClientField);
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ComboRow {
+            set_title: &self.header,
+            set_model: Some(&gtk::StringList::new(
+                &ClientField::ALL.map(|field| field.label())
+            )),
+            set_selected: ClientField::ALL.iter()
+                .position(|field| *field == self.field)
+                .unwrap_or_default() as u32,
+
+            connect_selected_notify[sender, index] => move |row| {
+                let field = ClientField::ALL[row.selected() as usize];
+                sender.output(MappingOutput::Changed(index.clone(), field)).unwrap()
+            },
+        }
+    }
+
+    fn init_model(
+        (header, field): Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { header, field }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+}
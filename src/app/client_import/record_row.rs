@@ -0,0 +1,113 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::app::Client;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportAction {
+    Add,
+    /// fill the empty fields of the duplicate
+    Merge,
+    Skip,
+}
+
+impl ImportAction {
+    fn label(&self) -> &'static str {
+        match self {
+            ImportAction::Add => "Ajouter",
+            ImportAction::Merge => "Compléter le client existant",
+            ImportAction::Skip => "Ignorer",
+        }
+    }
+}
+
+/// imported client, with the existing client it duplicates
+pub struct Model {
+    pub client: Client,
+    pub duplicate: Option<Client>,
+    pub action: ImportAction,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = u32;
+    /// the action changed
+    type Output = ();
+    /// action chosen before the records were read again, kept when still possible
+    type Init = (Client, Option<Client>, Option<ImportAction>);
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ComboRow {
+            set_title: &self.client.name,
+            set_subtitle: &self.summary(),
+            set_model: Some(&gtk::StringList::new(
+                &self.actions().iter().map(|action| action.label()).collect::<Vec<_>>()
+            )),
+            set_selected: self.actions().iter()
+                .position(|action| *action == self.action)
+                .unwrap_or_default() as u32,
+
+            connect_selected_notify[sender] => move |row| {
+                sender.input(row.selected());
+            },
+        }
+    }
+
+    fn init_model(
+        (client, duplicate, previous): Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        let mut model = Self { client, duplicate, action: ImportAction::Skip };
+        // duplicates are skipped unless asked otherwise
+        model.action = previous
+            .filter(|action| model.actions().contains(action))
+            .unwrap_or(model.actions()[0]);
+        model
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn update(&mut self, selected: Self::Input, sender: FactorySender<Self>) {
+        if let Some(action) = self.actions().get(selected as usize) {
+            self.action = *action;
+            sender.output(()).unwrap();
+        }
+    }
+}
+
+impl Model {
+    /// first action is the default one
+    fn actions(&self) -> &'static [ImportAction] {
+        match self.duplicate {
+            Some(_) => &[ImportAction::Skip, ImportAction::Merge, ImportAction::Add],
+            None => &[ImportAction::Add, ImportAction::Skip],
+        }
+    }
+
+    fn summary(&self) -> String {
+        let mut details = vec![
+            format!("{} {}", self.client.address.postcode, self.client.address.city),
+        ];
+        if let Some(siret) = &self.client.siret {
+            details.push(format!("SIRET {siret}"));
+        }
+        if let Some(duplicate) = &self.duplicate {
+            details.push(format!("déjà présent : {}", duplicate.name));
+        }
+        details.join(" · ")
+    }
+}
@@ -1,17 +1,41 @@
 use std::fmt;
+use std::path::PathBuf;
 
 use adw::prelude::*;
+use gtk::gio::Cancellable;
 use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
 use relm4_components::simple_adw_combo_row::{SimpleComboRow, SimpleComboRowMsg};
 
-use crate::app::{Client, ClientName};
+use crate::app::Client;
+use crate::app::client_export::{ClientExportInput, ClientExportModel, ClientExportOutput};
+use crate::app::client_import::{ClientImportInput, ClientImportModel, ClientImportOutput, ImportSource};
+use crate::archive::Archive;
+use crate::contacts;
+use crate::fuzzy;
+use crate::CFG;
+
+mod match_row;
+
+/// number of search results displayed, the list stays short whatever the number of clients
+const MAX_MATCHES: usize = 15;
 
 
 pub(crate) struct ClientSelectorGroupModel {
     client_list: Vec<Client>,
     client: Client,
-    client_row: Controller<SimpleComboRow<Client>>,
+    /// search results, or recent clients when the search is empty
+    matches: FactoryVecDeque<match_row::Model>,
+    search: String,
+    /// index in `client_list`
     current_index: Option<usize>,
+    show_archived: bool,
+    /// other clients which can be merged into the current one
+    merge_row: Controller<SimpleComboRow<Client>>,
+    /// index in `client_list` of the duplicate to merge
+    merge_index: Option<usize>,
+    import: Controller<ClientImportModel>,
+    export: Controller<ClientExportModel>,
 }
 
 #[derive(Debug)]
@@ -25,9 +49,30 @@ pub(crate) enum ClientSelectorGroupOutput {
 
 #[derive(Debug)]
 pub(crate) enum ClientSelectorGroupInput {
-    Selected(usize),
+    /// index in `client_list`
+    Select(usize),
+    Picked(DynamicIndex),
+    Search(String),
+    /// recent clients changed in the config
+    RecentChanged,
     Edited(Client),
     Created(Client),
+    /// ask to delete the current client
+    Delete,
+    /// delete the current client, or archive it when invoices reference it
+    DeleteConfirmed,
+    Restore,
+    ShowArchived(bool),
+    /// index in the merge candidates
+    MergeSelected(usize),
+    Merge,
+    MergeConfirmed,
+    /// pick a vcard or csv file to import
+    Import,
+    ImportFile(PathBuf),
+    Imported(Vec<Client>),
+    Export,
+    Exported,
 }
 
 #[relm4::component(pub(crate))]
@@ -37,7 +82,6 @@ impl SimpleComponent for ClientSelectorGroupModel {
     type Output = ClientSelectorGroupOutput;
 
     view! {
-        // combo row to select and existing client
         #[name(combo_row_group)]
         adw::PreferencesGroup {
             set_title: "Client",
@@ -58,6 +102,72 @@ impl SimpleComponent for ClientSelectorGroupModel {
                     },
                 },
 
+                append = &gtk::Button {
+                    set_tooltip: "Restaurer le client archivé",
+                    set_icon_name: "edit-undo-symbolic",
+                    set_focus_on_click: false,
+                    set_has_frame: false,
+                    set_has_tooltip: true,
+                    #[watch] set_visible: model.current_index.is_some() && model.client.archived,
+
+                    add_css_class: "circular",
+
+                    connect_clicked => ClientSelectorGroupInput::Restore,
+                },
+
+                append = &gtk::Button {
+                    set_tooltip: "Supprimer le client",
+                    set_icon_name: "user-trash-symbolic",
+                    set_focus_on_click: false,
+                    set_has_frame: false,
+                    set_has_tooltip: true,
+                    #[watch] set_sensitive: model.current_index.is_some(),
+                    #[watch] set_visible: !model.client.archived,
+
+                    add_css_class: "circular",
+
+                    connect_clicked => ClientSelectorGroupInput::Delete,
+                },
+
+                append = &gtk::ToggleButton {
+                    set_tooltip: "Afficher les clients archivés",
+                    set_icon_name: "view-reveal-symbolic",
+                    set_focus_on_click: false,
+                    set_has_frame: false,
+                    set_has_tooltip: true,
+
+                    add_css_class: "circular",
+
+                    connect_toggled[sender] => move |button| {
+                        sender.input(ClientSelectorGroupInput::ShowArchived(button.is_active()));
+                    },
+                },
+
+                append = &gtk::Button {
+                    set_tooltip: "Importer des clients (vCard, CSV)",
+                    set_icon_name: "document-open-symbolic",
+                    set_focus_on_click: false,
+                    set_has_frame: false,
+                    set_has_tooltip: true,
+
+                    add_css_class: "circular",
+
+                    connect_clicked => ClientSelectorGroupInput::Import,
+                },
+
+                append = &gtk::Button {
+                    set_tooltip: "Exporter des clients (vCard, CSV)",
+                    set_icon_name: "document-send-symbolic",
+                    set_focus_on_click: false,
+                    set_has_frame: false,
+                    set_has_tooltip: true,
+                    #[watch] set_sensitive: !model.client_list.is_empty(),
+
+                    add_css_class: "circular",
+
+                    connect_clicked => ClientSelectorGroupInput::Export,
+                },
+
                 append = &gtk::Button {
                     set_tooltip: "Nouveau client",
                     set_icon_name: "contact-new-symbolic",
@@ -73,7 +183,45 @@ impl SimpleComponent for ClientSelectorGroupModel {
                 },
             },
 
-            add = model.client_row.widget(),
+            add = &adw::ActionRow {
+                #[watch] set_title: &model.client.to_string(),
+                #[watch] set_subtitle: &model.display_client(),
+                #[watch] set_visible: model.current_index.is_some(),
+            },
+
+            add = &adw::ExpanderRow {
+                set_title: "Fusionner un doublon",
+                set_subtitle: "Les factures du doublon sont rattachées au client sélectionné",
+                #[watch] set_sensitive: model.current_index.is_some(),
+
+                add_row = model.merge_row.widget(),
+
+                add_row = &adw::ButtonRow {
+                    set_title: "Fusionner",
+                    #[watch] set_sensitive: model.merge_index.is_some(),
+                    connect_activated => ClientSelectorGroupInput::Merge,
+                },
+            },
+
+            add = &adw::EntryRow {
+                set_title: "Rechercher un client (nom, ville, SIRET)",
+                add_prefix = &gtk::Image {
+                    set_icon_name: Some("system-search-symbolic"),
+                },
+
+                connect_changed[sender] => move |entry| {
+                    sender.input(ClientSelectorGroupInput::Search(entry.text().to_string()));
+                },
+            },
+
+            // search results are listed after the rows of the group
+            #[local_ref]
+            add = matches_box -> gtk::ListBox {
+                add_css_class: "boxed-list",
+                set_selection_mode: gtk::SelectionMode::None,
+                set_margin_top: 12,
+                #[watch] set_visible: !model.matches.is_empty(),
+            },
         },
     }
 
@@ -83,29 +231,54 @@ impl SimpleComponent for ClientSelectorGroupModel {
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
 
-        let active_index = None;
-        let is_client_list_empty = client_list.is_empty();
+        let matches = FactoryVecDeque::builder()
+            .launch(gtk::ListBox::default())
+            .forward(sender.input_sender(), |output| match output {
+                match_row::MatchOutput::Picked(index) => ClientSelectorGroupInput::Picked(index),
+            });
 
-        let client_row: Controller<SimpleComboRow<Client>> =
+        let merge_row: Controller<SimpleComboRow<Client>> =
         SimpleComboRow::builder()
             .launch(SimpleComboRow {
-                variants: client_list.clone(),
-                active_index,
+                variants: Vec::new(),
+                active_index: None,
             })
-            .forward(sender.input_sender(), ClientSelectorGroupInput::Selected);
-
-
-        let model = ClientSelectorGroupModel {
+            .forward(sender.input_sender(), ClientSelectorGroupInput::MergeSelected);
+        merge_row.widget().set_title("Doublon");
+        merge_row.widget().set_enable_search(true);
+
+        let import = ClientImportModel::builder()
+            .launch(())
+            .forward(sender.input_sender(), |output| match output {
+                ClientImportOutput::Imported(clients) => ClientSelectorGroupInput::Imported(clients),
+            });
+
+        let export = ClientExportModel::builder()
+            .launch(())
+            .forward(sender.input_sender(), |output| match output {
+                ClientExportOutput::Exported => ClientSelectorGroupInput::Exported,
+            });
+
+        let mut model = ClientSelectorGroupModel {
             client_list,
             client: Client::default(),
-            client_row,
-            current_index: active_index,
+            matches,
+            search: String::new(),
+            current_index: None,
+            show_archived: false,
+            merge_row,
+            merge_index: None,
+            import,
+            export,
         };
+        model.update_matches();
 
-        if !is_client_list_empty {
-            sender.input_sender().emit(ClientSelectorGroupInput::Selected(0));
+        // start with the last invoiced client
+        if let Some(first) = model.matches.get(0) {
+            sender.input_sender().emit(ClientSelectorGroupInput::Select(first.index));
         }
 
+        let matches_box = model.matches.widget();
         let widgets = view_output!();
 
         ComponentParts { model, widgets }
@@ -116,24 +289,198 @@ impl SimpleComponent for ClientSelectorGroupModel {
             ClientSelectorGroupInput::Created(client) => {
                 self.client_list.push(client);
                 self.current_index = Some(self.client_list.len()-1);
-                self.update_combo();
+                self.update_matches();
                 // update client list
                 sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
                 // select the new client
-                sender.input(ClientSelectorGroupInput::Selected(self.current_index.unwrap()));
+                self.select(self.client_list.len()-1, &sender);
+                self.update_merge_combo();
             },
             ClientSelectorGroupInput::Edited(client) => {
                 self.client = client;
-                let index = self.current_index.expect("Shouldn't be able to edit when there's no client");
+                let index = self.client_list.iter()
+                    .position(|client| client.id == self.client.id)
+                    .expect("Shouldn't be able to edit a client that isn't listed");
                 self.client_list[index] = self.client.clone();
-                self.update_combo();
+                self.current_index = Some(index);
+                self.update_matches();
                 sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
                 sender.output(ClientSelectorGroupOutput::Selected(self.client.clone())).unwrap();
+                self.update_merge_combo();
+            },
+            ClientSelectorGroupInput::Select(index) => {
+                self.select(index, &sender);
+                self.update_merge_combo();
+            },
+            ClientSelectorGroupInput::Picked(index) => {
+                let Some(index) = self.matches.get(index.current_index()).map(|row| row.index) else { return };
+                self.select(index, &sender);
+                self.update_merge_combo();
+            },
+            ClientSelectorGroupInput::Search(search) => {
+                self.search = search;
+                self.update_matches();
+            },
+            ClientSelectorGroupInput::RecentChanged => self.update_matches(),
+            ClientSelectorGroupInput::Delete => {
+                let Some(index) = self.current_index else { return };
+                let name = &self.client_list[index].name;
+                let body = if self.referenced(index) {
+                    format!("« {name} » est cité par des factures archivées, il sera masqué de la liste et pourra être restauré.")
+                } else {
+                    format!("« {name} » sera définitivement supprimé.")
+                };
+                let dialog = adw::AlertDialog::new(Some("Supprimer le client ?"), Some(&body));
+                dialog.add_responses(&[("cancel", "Annuler"), ("delete", "Supprimer")]);
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response == "delete" {
+                        sender.input(ClientSelectorGroupInput::DeleteConfirmed);
+                    }
+                });
+                dialog.present(Some(self.merge_row.widget()));
             },
-            ClientSelectorGroupInput::Selected(index) => {
-                self.client = self.client_list[index].clone();
+            ClientSelectorGroupInput::DeleteConfirmed => {
+                let Some(index) = self.current_index else { return };
+                if self.referenced(index) {
+                    self.client_list[index].archived = true;
+                } else {
+                    self.client_list.remove(index);
+                }
+                self.current_index = None;
+                sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
+                match self.visible().first() {
+                    Some(&index) => self.select(index, &sender),
+                    None => {
+                        // no client left to select
+                        self.client = Client::default();
+                        sender.output(ClientSelectorGroupOutput::Selected(self.client.clone())).unwrap();
+                    },
+                }
+                self.update_matches();
+                self.update_merge_combo();
+            },
+            ClientSelectorGroupInput::Restore => {
+                let Some(index) = self.current_index else { return };
+                self.client_list[index].archived = false;
+                self.update_matches();
+                sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
+                self.select(index, &sender);
+            },
+            ClientSelectorGroupInput::ShowArchived(show) => {
+                self.show_archived = show;
+                if self.current_index.is_some_and(|index| !self.visible().contains(&index)) {
+                    self.current_index = None;
+                    if let Some(&index) = self.visible().first() {
+                        self.select(index, &sender);
+                    }
+                }
+                self.update_matches();
+                self.update_merge_combo();
+            },
+            ClientSelectorGroupInput::Import => {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some("vCard, CSV"));
+                for pattern in ["*.vcf", "*.vcard", "*.csv"] {
+                    filter.add_pattern(pattern);
+                }
+                let dialog = gtk::FileDialog::builder()
+                    .title("Importer des clients")
+                    .modal(true)
+                    .default_filter(&filter)
+                    .build();
+
+                let window = self.merge_row.widget().root().and_downcast::<gtk::Window>();
+                let sender = sender.clone();
+                dialog.open(window.as_ref(),
+                    Some(&Cancellable::new()),
+                    move |file| {
+                        if let Some(path) = file.ok().and_then(|file| file.path()) {
+                            sender.input(ClientSelectorGroupInput::ImportFile(path));
+                        }
+                    },
+                );
+            },
+            ClientSelectorGroupInput::ImportFile(path) => {
+                let is_csv = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+                let source = if is_csv {
+                    contacts::csv::read(&path).map(ImportSource::Table)
+                } else {
+                    contacts::vcard::read(&path).map(ImportSource::Clients)
+                };
+                match source {
+                    Ok(source) => self.import.emit(ClientImportInput::Open { source, existing: self.client_list.clone() }),
+                    Err(e) => {
+                        println!("Couldn't read clients from {}: {e}", path.display());
+                        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                        self.import.emit(ClientImportInput::ReadFailed(format!("{file_name} : {e}")));
+                    },
+                }
+                self.import.widget().present(Some(self.merge_row.widget()));
+            },
+            ClientSelectorGroupInput::Imported(clients) => {
+                self.import.widget().close();
+                for client in clients {
+                    match self.client_list.iter().position(|existing| existing.id == client.id) {
+                        Some(index) => self.client_list[index] = client,
+                        None => self.client_list.push(client),
+                    }
+                }
+                if let Some(index) = self.current_index {
+                    self.client = self.client_list[index].clone();
+                    sender.output(ClientSelectorGroupOutput::Selected(self.client.clone())).unwrap();
+                }
+                self.update_matches();
+                self.update_merge_combo();
+                sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
+            },
+            ClientSelectorGroupInput::Export => {
+                self.export.emit(ClientExportInput::Open(self.client_list.clone()));
+                self.export.widget().present(Some(self.merge_row.widget()));
+            },
+            ClientSelectorGroupInput::Exported => {
+                self.export.widget().close();
+            },
+            ClientSelectorGroupInput::MergeSelected(index) => {
+                self.merge_index = self.merge_candidates().get(index).copied();
+            },
+            ClientSelectorGroupInput::Merge => {
+                let (Some(target), Some(duplicate)) = (self.current_index, self.merge_index) else { return };
+                let target = &self.client_list[target].name;
+                let duplicate = &self.client_list[duplicate].name;
+                let dialog = adw::AlertDialog::new(
+                    Some("Fusionner les clients ?"),
+                    Some(&format!("Les factures de « {duplicate} » seront rattachées à « {target} », puis « {duplicate} » sera supprimé.")),
+                );
+                dialog.add_responses(&[("cancel", "Annuler"), ("merge", "Fusionner")]);
+                dialog.set_response_appearance("merge", adw::ResponseAppearance::Destructive);
+                let sender = sender.clone();
+                dialog.connect_response(None, move |_, response| {
+                    if response == "merge" {
+                        sender.input(ClientSelectorGroupInput::MergeConfirmed);
+                    }
+                });
+                dialog.present(Some(self.merge_row.widget()));
+            },
+            ClientSelectorGroupInput::MergeConfirmed => {
+                let (Some(target), Some(duplicate)) = (self.current_index, self.merge_index) else { return };
+                let target_id = self.client_list[target].id;
+                let reassigned = Archive::open()
+                    .and_then(|archive| archive.reassign_client(self.client_list[duplicate].id, target_id));
+                if let Err(e) = reassigned {
+                    println!("Couldn't merge clients: {e}");
+                    return;
+                }
+                self.client_list.remove(duplicate);
+                let index = self.client_list.iter()
+                    .position(|client| client.id == target_id)
+                    .expect("merge target should still be listed");
                 self.current_index = Some(index);
-                sender.output(ClientSelectorGroupOutput::Selected(self.client.clone())).unwrap();
+                self.update_matches();
+                sender.output(ClientSelectorGroupOutput::ClientListEdited(self.client_list.clone())).unwrap();
+                self.select(index, &sender);
+                self.update_merge_combo();
             },
             // TODO: test with only one client
 
@@ -152,12 +499,18 @@ impl SimpleComponent for ClientSelectorGroupModel {
             //     };
             // },
         }
-
-        self.client_row.widget().set_subtitle(&self.display_client());
     }
 }
 
 impl ClientSelectorGroupModel {
+    /// archived invoices reference the client, it can only be archived
+    fn referenced(&self, index: usize) -> bool {
+        Archive::open()
+            .and_then(|archive| archive.entries_of(self.client_list[index].id))
+            // keep the client when the archive can't be read
+            .map_or(true, |entries| !entries.is_empty())
+    }
+
     fn display_client(&self) -> String {
         // TODO: replace \\ with newline
         let name = self.client.name.clone();
@@ -186,24 +539,114 @@ impl ClientSelectorGroupModel {
             "".to_string()
         };
 
+        let contact = if let Some(s) = self.client.contact.clone() {
+            format!("\nContact: {s}")
+        } else {
+            "".to_string()
+        };
+
+        let email = if let Some(s) = self.client.email.clone() {
+            format!("\n{s}")
+        } else {
+            "".to_string()
+        };
+
+        let phone = if let Some(s) = self.client.phone.clone() {
+            format!("\n{s}")
+        } else {
+            "".to_string()
+        };
+
+        let delivery = if let Some(d) = self.client.delivery.clone() {
+            format!("\nLivraison: {}\n{}", d.name, d.address)
+        } else {
+            "".to_string()
+        };
+
         let custom_field = if let Some(s) = self.client.custom_field.clone() {
             format!("\n{s}")
         } else {
             "".to_string()
         };
 
-        format!("{name}\n{address}{siret}{code_ape}{tva}{tva_icc}{custom_field}")
+        format!("{name}\n{address}{contact}{email}{phone}{siret}{code_ape}{tva}{tva_icc}{delivery}{custom_field}")
+    }
+
+    /// select client at `index` in `client_list`
+    fn select(&mut self, index: usize, sender: &ComponentSender<Self>) {
+        self.client = self.client_list[index].clone();
+        self.current_index = Some(index);
+        sender.output(ClientSelectorGroupOutput::Selected(self.client.clone())).unwrap();
+    }
+
+    /// indices in `client_list` of the clients which can be picked
+    fn visible(&self) -> Vec<usize> {
+        (0..self.client_list.len())
+            .filter(|&i| self.show_archived || !self.client_list[i].archived)
+            .collect()
     }
 
-    fn update_combo(&self) {
+    /// indices in `client_list` of the clients which can be merged into the current one
+    fn merge_candidates(&self) -> Vec<usize> {
+        self.visible().into_iter()
+            .filter(|&i| Some(i) != self.current_index)
+            .collect()
+    }
+
+    /// list the best matches of the search, recent clients first
+    fn update_matches(&mut self) {
+        let recent = CFG.lock().unwrap().recent_clients.clone();
+        let recency = |client: &Client| recent.iter()
+            .position(|id| *id == client.id)
+            .unwrap_or(recent.len());
+
+        let words: Vec<String> = fuzzy::normalize(&self.search)
+            .split_whitespace()
+            .map(String::from)
+            .collect();
+
+        let mut found: Vec<(u32, usize)> = self.visible().into_iter()
+            .filter_map(|i| Some((match_score(&words, &self.client_list[i])?, i)))
+            .collect();
+        found.sort_by(|(score_a, a), (score_b, b)| {
+            let (a, b) = (&self.client_list[*a], &self.client_list[*b]);
+            score_b.cmp(score_a)
+                .then(recency(a).cmp(&recency(b)))
+                .then(a.name.cmp(&b.name))
+        });
+
+        let mut matches = self.matches.guard();
+        matches.clear();
+        for (_, i) in found.into_iter().take(MAX_MATCHES) {
+            matches.push_back((self.client_list[i].clone(), i));
+        }
+    }
+
+    fn update_merge_combo(&mut self) {
+        self.merge_index = None;
         let updated_combo = SimpleComboRow {
-            variants: self.client_list.clone(),
-            active_index: self.current_index,
+            variants: self.merge_candidates().into_iter().map(|i| self.client_list[i].clone()).collect(),
+            active_index: None,
         };
 
-        self.client_row
+        self.merge_row
             .sender()
             .send(SimpleComboRowMsg::UpdateData(updated_combo))
             .unwrap();
     }
 }
+
+/// every word of the search must match the name, city or SIRET of the client
+fn match_score(words: &[String], client: &Client) -> Option<u32> {
+    let name = fuzzy::normalize(&client.name);
+    let city = fuzzy::normalize(&client.address.city);
+    let siret: String = client.siret.iter().flat_map(|s| s.chars()).filter(|c| !c.is_whitespace()).collect();
+
+    words.iter()
+        .map(|word| {
+            [&name, &city, &siret].into_iter()
+                .filter_map(|field| fuzzy::score(word, field))
+                .max()
+        })
+        .sum()
+}
@@ -0,0 +1,77 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::app::Client;
+
+/// client found by the search, `index` is its position in the client list
+pub struct Model {
+    pub client: Client,
+    pub index: usize,
+}
+
+#[derive(Debug)]
+pub enum MatchOutput {
+    Picked(DynamicIndex),
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = gtk::ListBox;
+    type Input = ();
+    type Output = MatchOutput;
+    type Init = (Client, usize);
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        adw::ActionRow {
+            set_title: &self.client.to_string(),
+            set_subtitle: &self.summary(),
+            set_activatable: true,
+
+            add_suffix = &gtk::Image {
+                set_icon_name: Some("go-next-symbolic"),
+            },
+
+            connect_activated[sender, index] => move |_| {
+                sender.output(MatchOutput::Picked(index.clone())).unwrap()
+            },
+        }
+    }
+
+    fn init_model(
+        (client, index): Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { client, index }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+}
+
+impl Model {
+    /// one line of details to tell homonyms apart
+    fn summary(&self) -> String {
+        let mut details = vec![
+            format!("{} {}", self.client.address.postcode, self.client.address.city),
+        ];
+        if let Some(siret) = &self.client.siret {
+            details.push(format!("SIRET {siret}"));
+        }
+        if let Some(contact) = &self.client.contact {
+            details.push(contact.clone());
+        }
+        details.join(" · ")
+    }
+}
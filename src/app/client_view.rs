@@ -3,7 +3,7 @@ use relm4::prelude::*;
 
 use crate::app::client_selector_group::{ClientSelectorGroupModel, ClientSelectorGroupInput, ClientSelectorGroupOutput};
 use crate::app::{ClientFormModel, ClientFormOutput};
-use crate::app::Client;
+use crate::app::{Client, ClientDefaults, Product};
 
 use super::client_form::ClientFormInput;
 
@@ -20,6 +20,8 @@ pub(crate) struct ClientViewModel {
 pub(crate) enum ClientViewOutput {
     ClientListEdited(Vec<Client>),
     Selected(Client),
+    /// a different client was picked, its defaults apply to the invoice
+    Defaults(ClientDefaults),
 }
 
 #[derive(Debug)]
@@ -31,6 +33,10 @@ pub(crate) enum ClientViewInput {
     Edited(Client),
     Created(Client),
     ClosedForm,
+    /// products of the invoice being edited
+    Products(Vec<Product>),
+    /// a client was invoiced, recent clients are listed first
+    RecentChanged,
 }
 
 #[relm4::component(pub(crate))]
@@ -102,11 +108,17 @@ impl SimpleComponent for ClientViewModel {
             ClientViewInput::ClosedForm => {
                 self.show_edit = false;
             },
+            ClientViewInput::Products(products) => {
+                self.client_form.emit(ClientFormInput::CurrentProducts(products));
+            },
             ClientViewInput::ClientListEdited(client_list) => {
                 // FIXME: use a singleton instead?
                 self.clients = client_list.clone();
                 sender.output(ClientViewOutput::ClientListEdited(client_list)).unwrap();
             },
+            ClientViewInput::RecentChanged => {
+                self.client_selector.sender().emit(ClientSelectorGroupInput::RecentChanged);
+            },
             ClientViewInput::Edited(client) => {
                 self.show_edit = false;
                 self.client_selector.sender().emit(ClientSelectorGroupInput::Edited(client));
@@ -116,6 +128,9 @@ impl SimpleComponent for ClientViewModel {
                 self.client_selector.sender().emit(ClientSelectorGroupInput::Created(client));
             },
             ClientViewInput::Selected(client) => {
+                if self.current_client.as_ref().is_none_or(|current| current.id != client.id) {
+                    sender.output(ClientViewOutput::Defaults(client.defaults.clone())).unwrap();
+                }
                 self.current_client = Some(client.clone());
                 sender.output(ClientViewOutput::Selected(client)).unwrap();
             },
@@ -0,0 +1,194 @@
+use adw::prelude::*;
+use chrono::{Datelike, Local};
+use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
+
+use crate::archive::{Archive, Entry};
+use crate::money::Money;
+use crate::stats::{self, Period, QuoteRate};
+use crate::CFG;
+
+mod revenue_row;
+
+
+/// revenue, clients and thresholds of a year, computed from the archive
+pub(crate) struct DashboardModel {
+    entries: Vec<Entry>,
+    year: i32,
+    period: Period,
+    periods: FactoryVecDeque<revenue_row::Model>,
+    clients: FactoryVecDeque<revenue_row::Model>,
+}
+
+#[derive(Debug)]
+pub(crate) enum DashboardInput {
+    /// the archive or the payments changed
+    Reload,
+    Year(i32),
+    /// index in `Period::ALL`
+    Period(u32),
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for DashboardModel {
+    type Init = ();
+    type Input = DashboardInput;
+    type Output = ();
+
+    view! {
+        adw::PreferencesPage {
+            add = &adw::PreferencesGroup {
+                set_title: "Activité",
+
+                add = &adw::SpinRow {
+                    set_title: "Année",
+                    set_adjustment: Some(&gtk::Adjustment::new(model.year as f64, 2000.0, 2100.0, 1.0, 1.0, 0.0)),
+                    connect_value_notify[sender] => move |row| {
+                        sender.input(DashboardInput::Year(row.value() as i32));
+                    },
+                },
+
+                add = &adw::ActionRow {
+                    set_title: "Chiffre d'affaires",
+                    #[watch] set_subtitle: &{
+                        let revenue = stats::of_year(&model.entries, model.year);
+                        format!("facturé {} € · encaissé {} €", revenue.invoiced, revenue.collected)
+                    },
+                },
+
+                add = &adw::ActionRow {
+                    set_title: "Devis acceptés",
+                    #[watch] set_subtitle: &quote_summary(stats::quote_rate(&model.entries, model.year)),
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                set_title: "Seuils",
+                set_description: Some("Chiffre d'affaires encaissé dans l'année"),
+
+                add = &adw::ActionRow {
+                    set_title: "Micro-entreprise",
+                    #[watch] set_subtitle: &model.threshold_summary(model.thresholds().micro_entreprise),
+
+                    add_suffix = &gtk::LevelBar {
+                        set_valign: gtk::Align::Center,
+                        set_width_request: 150,
+                        set_max_value: 1.0,
+                        #[watch] set_value: model.threshold_share(model.thresholds().micro_entreprise),
+                    },
+                },
+
+                add = &adw::ActionRow {
+                    set_title: "Franchise en base de TVA",
+                    #[watch] set_subtitle: &model.threshold_summary(model.thresholds().vat_franchise),
+
+                    add_suffix = &gtk::LevelBar {
+                        set_valign: gtk::Align::Center,
+                        set_width_request: 150,
+                        set_max_value: 1.0,
+                        #[watch] set_value: model.threshold_share(model.thresholds().vat_franchise),
+                    },
+                },
+            },
+
+            #[local_ref]
+            add = periods_box -> adw::PreferencesGroup {
+                set_title: "Chiffre d'affaires par période",
+
+                #[wrap(Some)]
+                set_header_suffix = &gtk::DropDown {
+                    set_model: Some(&gtk::StringList::new(
+                        &Period::ALL.map(|period| period.label())
+                    )),
+                    connect_selected_notify[sender] => move |dropdown| {
+                        sender.input(DashboardInput::Period(dropdown.selected()));
+                    },
+                },
+            },
+
+            #[local_ref]
+            add = clients_box -> adw::PreferencesGroup {
+                set_title: "Chiffre d'affaires par client",
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = DashboardModel {
+            entries: Vec::new(),
+            year: Local::now().year(),
+            period: Period::Month,
+            periods: FactoryVecDeque::builder()
+                .launch(adw::PreferencesGroup::default())
+                .detach(),
+            clients: FactoryVecDeque::builder()
+                .launch(adw::PreferencesGroup::default())
+                .detach(),
+        };
+        sender.input(DashboardInput::Reload);
+
+        let periods_box = model.periods.widget();
+        let clients_box = model.clients.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            DashboardInput::Reload => {
+                match Archive::open().and_then(|archive| archive.entries()) {
+                    Ok(entries) => self.entries = entries,
+                    Err(e) => println!("Couldn't read archive: {e}"),
+                }
+            },
+            DashboardInput::Year(year) => self.year = year,
+            DashboardInput::Period(index) => {
+                self.period = Period::ALL[index as usize];
+            },
+        }
+
+        let mut periods = self.periods.guard();
+        periods.clear();
+        for revenue in stats::by_period(&self.entries, self.period, self.year) {
+            periods.push_back(revenue);
+        }
+        drop(periods);
+
+        let mut clients = self.clients.guard();
+        clients.clear();
+        for revenue in stats::by_client(&self.entries, self.year) {
+            clients.push_back(revenue);
+        }
+    }
+}
+
+impl DashboardModel {
+    fn thresholds(&self) -> stats::Thresholds {
+        CFG.lock().unwrap().thresholds(self.year)
+    }
+
+    /// thresholds can be set to zero in the config, there is nothing to reach then
+    fn threshold_share(&self, threshold: Money) -> f64 {
+        if threshold <= Money::ZERO { return 0.0; }
+        (stats::collected(&self.entries, self.year).euros() / threshold.euros()).clamp(0.0, 1.0)
+    }
+
+    fn threshold_summary(&self, threshold: Money) -> String {
+        let collected = stats::collected(&self.entries, self.year);
+        if threshold <= Money::ZERO {
+            return format!("{collected} €, aucun plafond défini");
+        }
+        format!("{collected} € sur {:.0} € ({:.0} %)", threshold.euros(), 100.0 * collected.euros() / threshold.euros())
+    }
+}
+
+fn quote_summary(rate: QuoteRate) -> String {
+    match rate.percent() {
+        Some(percent) => format!("{} sur {} ({percent:.0} %)", rate.accepted, rate.issued),
+        None => "Aucun devis".to_string(),
+    }
+}
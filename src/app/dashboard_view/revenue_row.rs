@@ -0,0 +1,64 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::money::Money;
+use crate::stats::Revenue;
+
+/// revenue of a period or a client, with the share of it collected
+pub struct Model {
+    revenue: Revenue,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = ();
+    type Output = ();
+    type Init = Revenue;
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ActionRow {
+            set_title: &self.revenue.label,
+            set_subtitle: &format!("facturé {} € · encaissé {} €", self.revenue.invoiced, self.revenue.collected),
+
+            add_suffix = &gtk::LevelBar {
+                set_valign: gtk::Align::Center,
+                set_width_request: 150,
+                set_max_value: 1.0,
+                set_value: self.collected_share(),
+                set_tooltip: "Part encaissée",
+            },
+        }
+    }
+
+    fn init_model(
+        revenue: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { revenue }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+}
+
+impl Model {
+    fn collected_share(&self) -> f64 {
+        if self.revenue.invoiced <= Money::ZERO {
+            return 0.0;
+        }
+        (self.revenue.collected.euros() / self.revenue.invoiced.euros()).clamp(0.0, 1.0)
+    }
+}
@@ -0,0 +1,108 @@
+use adw::prelude::*;
+use chrono::NaiveDate;
+use gtk::glib;
+use relm4::prelude::*;
+
+
+/// action row displaying a date, with a calendar popover to pick it
+pub(crate) struct DateRowModel {
+    title: String,
+    date: NaiveDate,
+    /// date was set by the parent component, calendar needs to be updated
+    set_externally: bool,
+}
+
+pub(crate) struct DateRowInit {
+    pub title: String,
+    pub date: NaiveDate,
+}
+
+#[derive(Debug)]
+pub(crate) enum DateRowInput {
+    /// set date without notifying the parent component
+    Set(NaiveDate),
+    Selected(NaiveDate), // FIXME: should be private
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for DateRowModel {
+    type Init = DateRowInit;
+    type Input = DateRowInput;
+    type Output = NaiveDate;
+
+    view! {
+        #[root]
+        adw::ActionRow {
+            set_title: &model.title,
+            #[watch] set_subtitle: &model.date.format("%d/%m/%Y").to_string(),
+
+            add_suffix = &gtk::MenuButton {
+                set_icon_name: "x-office-calendar-symbolic",
+                set_tooltip: "Choisir une date",
+                set_valign: gtk::Align::Center,
+                add_css_class: "flat",
+
+                #[wrap(Some)]
+                set_popover = &gtk::Popover {
+                    gtk::Calendar {
+                        #[track(model.set_externally)]
+                        #[block_signal(day_handler)]
+                        select_day: &to_glib_date(model.date),
+
+                        connect_day_selected[sender] => move |calendar| {
+                            let date = calendar.date();
+                            if let Some(date) = NaiveDate::from_ymd_opt(
+                                date.year(),
+                                date.month() as u32,
+                                date.day_of_month() as u32,
+                            ) {
+                                sender.input(DateRowInput::Selected(date));
+                            }
+                        } @day_handler,
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = DateRowModel {
+            title: init.title,
+            date: init.date,
+            set_externally: true,
+        };
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            DateRowInput::Set(date) => {
+                self.set_externally = true;
+                self.date = date;
+            },
+            DateRowInput::Selected(date) => {
+                self.set_externally = false;
+                self.date = date;
+                sender.output(self.date).unwrap();
+            },
+        }
+    }
+}
+
+impl DateRowModel {
+    pub(crate) fn date(&self) -> NaiveDate {
+        self.date
+    }
+}
+
+fn to_glib_date(date: NaiveDate) -> glib::DateTime {
+    use chrono::Datelike;
+    glib::DateTime::from_local(date.year(), date.month() as i32, date.day() as i32, 0, 0, 0.0)
+        .expect("a valid chrono date should be a valid glib date")
+}
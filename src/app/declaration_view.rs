@@ -0,0 +1,217 @@
+use adw::prelude::*;
+use chrono::{Datelike, Local, NaiveDate};
+use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
+
+use crate::archive::{Archive, Entry};
+use crate::declaration::{self, Activity, Declaration};
+use crate::money::Money;
+use crate::stats::Period;
+
+mod diffuseur_row;
+
+
+/// amounts to report on the URSSAF declaration of a period
+pub(crate) struct DeclarationModel {
+    entries: Vec<Entry>,
+    frequency: Period,
+    year: i32,
+    /// index of the period in the year
+    index: usize,
+    /// periods of the combo row need to be rebuilt
+    periods_changed: bool,
+    declaration: Declaration,
+    diffuseurs: FactoryVecDeque<diffuseur_row::Model>,
+}
+
+#[derive(Debug)]
+pub(crate) enum DeclarationInput {
+    /// the archive or the payments changed
+    Reload,
+    /// index in `Period::ALL`
+    Frequency(u32),
+    Year(i32),
+    Period(u32),
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for DeclarationModel {
+    type Init = ();
+    type Input = DeclarationInput;
+    type Output = ();
+
+    view! {
+        adw::PreferencesPage {
+            add = &adw::PreferencesGroup {
+                set_title: "Déclaration URSSAF",
+                set_description: Some("Chiffre d'affaires encaissé sur la période, d'après les règlements enregistrés"),
+
+                add = &adw::ComboRow {
+                    set_title: "Déclaration par",
+                    set_model: Some(&gtk::StringList::new(
+                        &Period::ALL.map(|period| period.label())
+                    )),
+                    set_selected: Period::ALL.iter().position(|period| *period == model.frequency).unwrap() as u32,
+                    connect_selected_notify[sender] => move |row| {
+                        sender.input(DeclarationInput::Frequency(row.selected()));
+                    },
+                },
+
+                add = &adw::SpinRow {
+                    set_title: "Année",
+                    set_adjustment: Some(&gtk::Adjustment::new(model.year as f64, 2000.0, 2100.0, 1.0, 1.0, 0.0)),
+                    connect_value_notify[sender] => move |row| {
+                        sender.input(DeclarationInput::Year(row.value() as i32));
+                    },
+                },
+
+                add = &adw::ComboRow {
+                    set_title: "Période",
+                    #[watch] set_visible: model.frequency != Period::Year,
+
+                    #[track(model.periods_changed)]
+                    #[block_signal(period_handler)]
+                    set_model: Some(&gtk::StringList::new(
+                        &model.frequency.periods(model.year).iter()
+                            .map(|(name, _, _)| name.as_str())
+                            .collect::<Vec<_>>()
+                    )),
+
+                    #[track(model.periods_changed)]
+                    #[block_signal(period_handler)]
+                    set_selected: model.index as u32,
+
+                    connect_selected_notify[sender] => move |row| {
+                        sender.input(DeclarationInput::Period(row.selected()));
+                    } @period_handler,
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                set_title: "Montants à déclarer",
+                set_description: Some("Montants bruts, avant précompte"),
+
+                add = &adw::ActionRow {
+                    set_title: Activity::Withheld.label(),
+                    set_subtitle: "Cotisations déjà versées par les diffuseurs",
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €", model.declaration.activity(Activity::Withheld)),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: Activity::Dispensed.label(),
+                    set_subtitle: "Cotisations à payer",
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €", model.declaration.activity(Activity::Dispensed)),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: Activity::Direct.label(),
+                    set_subtitle: "Cotisations à payer",
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €", model.declaration.activity(Activity::Direct)),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: "Dont cessions de droits d'auteur",
+                    add_suffix = &gtk::Label {
+                        #[watch] set_label: &format!("{} €", model.declaration.rights),
+                    },
+                },
+                add = &adw::ActionRow {
+                    set_title: "Total encaissé",
+                    add_suffix = &gtk::Label {
+                        add_css_class: "heading",
+                        #[watch] set_label: &format!("{} €", model.declaration.total),
+                    },
+                },
+            },
+
+            #[local_ref]
+            add = diffuseurs_box -> adw::PreferencesGroup {
+                set_title: "Contributions versées par les diffuseurs",
+                #[watch] set_description: Some(&model.diffuseurs_summary()),
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let frequency = Period::Quarter;
+        let (year, index) = last_completed(frequency, Local::now().date_naive());
+        let model = DeclarationModel {
+            entries: Vec::new(),
+            frequency,
+            year,
+            index,
+            periods_changed: true,
+            declaration: Declaration::default(),
+            diffuseurs: FactoryVecDeque::builder()
+                .launch(adw::PreferencesGroup::default())
+                .detach(),
+        };
+        sender.input(DeclarationInput::Reload);
+
+        let diffuseurs_box = model.diffuseurs.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        self.periods_changed = false;
+        match message {
+            DeclarationInput::Reload => {
+                match Archive::open().and_then(|archive| archive.entries()) {
+                    Ok(entries) => self.entries = entries,
+                    Err(e) => println!("Couldn't read archive: {e}"),
+                }
+            },
+            DeclarationInput::Frequency(index) => {
+                self.frequency = Period::ALL[index as usize];
+                let (year, index) = last_completed(self.frequency, Local::now().date_naive());
+                self.index = if year == self.year { index } else { 0 };
+                self.periods_changed = true;
+            },
+            DeclarationInput::Year(year) => {
+                self.year = year;
+                self.periods_changed = true;
+            },
+            DeclarationInput::Period(index) => self.index = index as usize,
+        }
+
+        let (_, start, end) = self.frequency.periods(self.year)
+            .into_iter()
+            .nth(self.index)
+            .expect("period index should be one of the periods of the year");
+        self.declaration = declaration::declare(&self.entries, start, end);
+
+        let mut diffuseurs = self.diffuseurs.guard();
+        diffuseurs.clear();
+        for payment in self.declaration.diffuseurs.clone() {
+            diffuseurs.push_back(payment);
+        }
+    }
+}
+
+impl DeclarationModel {
+    fn diffuseurs_summary(&self) -> String {
+        let contributions: Money = self.declaration.diffuseurs.iter().map(|payment| payment.contributions).sum();
+        let withheld: Money = self.declaration.diffuseurs.iter().map(|payment| payment.withheld).sum();
+        match self.declaration.diffuseurs.len() {
+            0 => "Aucune facture de diffuseur encaissée sur la période".to_string(),
+            _ => format!("Contributions diffuseur {contributions} € · précompte {withheld} €"),
+        }
+    }
+}
+
+/// last period that ended before the date, the one usually declared
+fn last_completed(frequency: Period, date: NaiveDate) -> (i32, usize) {
+    let periods = frequency.periods(date.year());
+    match periods.iter().position(|(_, start, end)| (*start..=*end).contains(&date)) {
+        Some(index) if index > 0 => (date.year(), index - 1),
+        _ => (date.year() - 1, periods.len() - 1),
+    }
+}
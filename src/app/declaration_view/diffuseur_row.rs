@@ -0,0 +1,62 @@
+use adw::prelude::*;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::declaration::DiffuseurPayment;
+use crate::money::Money;
+
+/// contributions a diffuseur already paid on an invoice
+pub struct Model {
+    payment: DiffuseurPayment,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = ();
+    type Output = ();
+    type Init = DiffuseurPayment;
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ActionRow {
+            set_title: &format!("Facture {} · {}", self.payment.number, self.payment.client),
+            set_subtitle: &self.details(),
+
+            add_suffix = &gtk::Label {
+                set_label: &format!("{} €", self.payment.collected),
+                set_tooltip: "Montant brut encaissé",
+            },
+        }
+    }
+
+    fn init_model(
+        payment: Self::Init,
+        _index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        Self { payment }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+}
+
+impl Model {
+    fn details(&self) -> String {
+        let mut details = vec![format!("contributions diffuseur {} €", self.payment.contributions)];
+        if self.payment.withheld > Money::ZERO {
+            details.push(format!("précompte {} €", self.payment.withheld));
+        }
+        details.join(" · ")
+    }
+}
@@ -0,0 +1,346 @@
+use std::path::PathBuf;
+
+use adw::prelude::*;
+use chrono::{Datelike, Local, NaiveDate};
+use gtk::gio::Cancellable;
+use relm4::prelude::*;
+use relm4::factory::FactoryVecDeque;
+
+use crate::app::date_row::{DateRowInit, DateRowModel};
+use crate::archive::{Archive, Entry};
+use crate::fec::{self, Account, Accounts};
+use crate::ledger::{self, Receipt};
+use crate::money::Money;
+use crate::{APP_NAME, CFG};
+
+mod invoice_row;
+
+
+/// payments of the archived invoices, exported as the livre des recettes
+/// or as the accounting entries of the FEC
+pub(crate) struct LedgerModel {
+    invoices: FactoryVecDeque<invoice_row::Model>,
+    start_row: Controller<DateRowModel>,
+    end_row: Controller<DateRowModel>,
+    start: NaiveDate,
+    end: NaiveDate,
+    format: LedgerFormat,
+    accounts: Accounts,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LedgerFormat {
+    Csv,
+    Ods,
+    Pdf,
+}
+
+impl LedgerFormat {
+    /// in the same order as the format combo row
+    const ALL: [LedgerFormat; 3] = [LedgerFormat::Csv, LedgerFormat::Ods, LedgerFormat::Pdf];
+
+    fn label(&self) -> &'static str {
+        match self {
+            LedgerFormat::Csv => "CSV",
+            LedgerFormat::Ods => "ODS (LibreOffice)",
+            LedgerFormat::Pdf => "PDF",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            LedgerFormat::Csv => "csv",
+            LedgerFormat::Ods => "ods",
+            LedgerFormat::Pdf => "pdf",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum LedgerInput {
+    /// the archive changed
+    Reload,
+    Start(NaiveDate),
+    End(NaiveDate),
+    /// index in `LedgerFormat::ALL`
+    Format(u32),
+    PaymentsEdited(DynamicIndex),
+    Export,
+    Save(PathBuf),
+    AccountEdited(Account, String),
+    ExportFec,
+    SaveFec(PathBuf),
+}
+
+#[relm4::component(pub(crate))]
+impl SimpleComponent for LedgerModel {
+    type Init = ();
+    type Input = LedgerInput;
+    /// payments were recorded or cancelled
+    type Output = ();
+
+    view! {
+        adw::PreferencesPage {
+            add = &adw::PreferencesGroup {
+                set_title: "Livre des recettes",
+                set_description: Some("Règlements reçus pour les factures archivées, par date d'encaissement"),
+
+                add = model.start_row.widget(),
+                add = model.end_row.widget(),
+
+                add = &adw::ComboRow {
+                    set_title: "Format",
+                    set_model: Some(&gtk::StringList::new(
+                        &LedgerFormat::ALL.map(|format| format.label())
+                    )),
+                    connect_selected_notify[sender] => move |row| {
+                        sender.input(LedgerInput::Format(row.selected()));
+                    },
+                },
+
+                add = &adw::ActionRow {
+                    set_title: "Total encaissé",
+                    #[watch] set_subtitle: &{
+                        let receipts = model.receipts();
+                        format!("{} € · {} règlements", ledger::total(&receipts), receipts.len())
+                    },
+
+                    add_suffix = &gtk::Button {
+                        set_label: "Exporter",
+                        set_valign: gtk::Align::Center,
+                        add_css_class: "suggested-action",
+                        #[watch] set_sensitive: model.start <= model.end,
+                        connect_clicked => LedgerInput::Export,
+                    },
+                },
+            },
+
+            add = &adw::PreferencesGroup {
+                set_title: "Fichier des écritures comptables",
+                set_description: Some("Écritures des factures et règlements de la période, au format FEC de l'administration fiscale"),
+
+                add = &adw::ExpanderRow {
+                    set_title: "Numéros de compte",
+
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Clients.label(),
+                        set_text: &Account::Clients.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Clients, row.text().to_string()));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Revenue.label(),
+                        set_text: &Account::Revenue.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Revenue, row.text().to_string()));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Vat.label(),
+                        set_text: &Account::Vat.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Vat, row.text().to_string()));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Bank.label(),
+                        set_text: &Account::Bank.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Bank, row.text().to_string()));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Cash.label(),
+                        set_text: &Account::Cash.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Cash, row.text().to_string()));
+                        },
+                    },
+                    add_row = &adw::EntryRow {
+                        set_title: Account::Withholding.label(),
+                        set_text: &Account::Withholding.get(&model.accounts),
+                        connect_changed[sender] => move |row| {
+                            sender.input(LedgerInput::AccountEdited(Account::Withholding, row.text().to_string()));
+                        },
+                    },
+                },
+
+                add = &adw::ActionRow {
+                    set_title: "FEC",
+                    #[watch] set_subtitle: &fec::file_name(&model.siret(), model.end),
+
+                    add_suffix = &gtk::Button {
+                        set_label: "Exporter",
+                        set_valign: gtk::Align::Center,
+                        #[watch] set_sensitive: model.start <= model.end,
+                        connect_clicked => LedgerInput::ExportFec,
+                    },
+                },
+            },
+
+            #[local_ref]
+            add = invoices_box -> adw::PreferencesGroup {
+                set_title: "Factures",
+                #[watch] set_description: Some(&model.unpaid_summary()),
+            },
+        }
+    }
+
+    fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let invoices = FactoryVecDeque::builder()
+            .launch(adw::PreferencesGroup::default())
+            .forward(sender.input_sender(), LedgerInput::PaymentsEdited);
+
+        // current civil year, as declared
+        let today = Local::now().date_naive();
+        let start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap();
+        let start_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Du".to_string(), date: start })
+            .forward(sender.input_sender(), LedgerInput::Start);
+        let end_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Au".to_string(), date: end })
+            .forward(sender.input_sender(), LedgerInput::End);
+
+        let model = LedgerModel {
+            invoices,
+            start_row,
+            end_row,
+            start,
+            end,
+            format: LedgerFormat::Csv,
+            accounts: CFG.lock().unwrap().accounts.clone(),
+        };
+        sender.input(LedgerInput::Reload);
+
+        let invoices_box = model.invoices.widget();
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            LedgerInput::Reload => {
+                let entries = match Archive::open().and_then(|archive| archive.entries()) {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        println!("Couldn't read archive: {e}");
+                        return;
+                    },
+                };
+                let mut invoices = self.invoices.guard();
+                invoices.clear();
+                // most recent first
+                for entry in entries.into_iter().rev().filter(|entry| !entry.invoice.is_devis) {
+                    invoices.push_back(entry);
+                }
+            },
+            LedgerInput::Start(date) => self.start = date,
+            LedgerInput::End(date) => self.end = date,
+            LedgerInput::Format(index) => {
+                self.format = LedgerFormat::ALL[index as usize];
+            },
+            LedgerInput::PaymentsEdited(index) => {
+                let Some(row) = self.invoices.get(index.current_index()) else { return };
+                if let Err(e) = Archive::open()
+                    .and_then(|archive| archive.set_payments(&row.entry.invoice, row.entry.payments.clone())) {
+                    println!("Couldn't store payments of {}: {e}", row.entry.invoice.number);
+                }
+                sender.output(()).unwrap();
+            },
+            LedgerInput::Export => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Exporter le livre des recettes")
+                    .modal(true)
+                    .initial_name(format!("livre-des-recettes-{}.{}",
+                        self.start.format("%Y-%m-%d"), self.format.extension()))
+                    .build();
+
+                let window = self.invoices.widget().root().and_downcast::<gtk::Window>();
+                let sender = sender.clone();
+                dialog.save(window.as_ref(),
+                    Some(&Cancellable::new()),
+                    move |file| {
+                        if let Some(path) = file.ok().and_then(|file| file.path()) {
+                            sender.input(LedgerInput::Save(path));
+                        }
+                    },
+                );
+            },
+            LedgerInput::AccountEdited(account, number) => {
+                account.set(&mut self.accounts, number);
+                CFG.lock().unwrap().accounts = self.accounts.clone();
+                confy::store(APP_NAME, None, CFG.lock().unwrap().clone()).unwrap();
+            },
+            LedgerInput::ExportFec => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Exporter le fichier des écritures comptables")
+                    .modal(true)
+                    .initial_name(fec::file_name(&self.siret(), self.end))
+                    .build();
+
+                let window = self.invoices.widget().root().and_downcast::<gtk::Window>();
+                let sender = sender.clone();
+                dialog.save(window.as_ref(),
+                    Some(&Cancellable::new()),
+                    move |file| {
+                        if let Some(path) = file.ok().and_then(|file| file.path()) {
+                            sender.input(LedgerInput::SaveFec(path));
+                        }
+                    },
+                );
+            },
+            LedgerInput::SaveFec(path) => {
+                let ecritures = fec::ecritures(&self.entries(), &self.accounts, self.start, self.end);
+                if let Err(e) = fec::write_file(&path, &ecritures) {
+                    println!("Couldn't export FEC to {}: {e}", path.display());
+                }
+            },
+            LedgerInput::Save(path) => {
+                let receipts = self.receipts();
+                let written = match self.format {
+                    LedgerFormat::Csv => ledger::csv::write_file(&path, &receipts),
+                    LedgerFormat::Ods => ledger::ods::write_file(&path, &receipts),
+                    LedgerFormat::Pdf => {
+                        let author = CFG.lock().unwrap().author.clone().unwrap_or_default();
+                        ledger::pdf::write_file(&path, &receipts, &author, self.start, self.end)
+                    },
+                };
+                if let Err(e) = written {
+                    println!("Couldn't export ledger to {}: {e}", path.display());
+                }
+            },
+        }
+    }
+}
+
+impl LedgerModel {
+    fn entries(&self) -> Vec<Entry> {
+        self.invoices.iter().map(|row| row.entry.clone()).collect()
+    }
+
+    fn receipts(&self) -> Vec<Receipt> {
+        ledger::receipts(&self.entries(), self.start, self.end)
+    }
+
+    fn siret(&self) -> String {
+        CFG.lock().unwrap().author.as_ref().map(|author| author.siret.clone()).unwrap_or_default()
+    }
+
+    fn unpaid_summary(&self) -> String {
+        let unpaid: Vec<Money> = self.invoices.iter()
+            .map(|row| row.entry.remaining())
+            .filter(|remaining| *remaining > Money::ZERO)
+            .collect();
+        match unpaid.len() {
+            0 => "Toutes les factures sont réglées".to_string(),
+            count => format!("{count} factures en attente de règlement, {} € restant",
+                unpaid.iter().sum::<Money>()),
+        }
+    }
+}
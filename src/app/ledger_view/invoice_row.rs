@@ -0,0 +1,198 @@
+use adw::prelude::*;
+use chrono::Local;
+use relm4::prelude::*;
+use relm4::factory::FactoryView;
+
+use crate::app::date_row::{DateRowInit, DateRowModel};
+use crate::archive::{Entry, Payment, PaymentMethod};
+use crate::money::Money;
+
+/// archived invoice, with a form to record the payments received
+pub struct Model {
+    pub entry: Entry,
+    method: PaymentMethod,
+    amount: String,
+    /// amount entry is reset to the remaining amount
+    reset_amount: bool,
+    date_row: Controller<DateRowModel>,
+    index: DynamicIndex,
+}
+
+#[derive(Debug)]
+pub enum Input {
+    /// index in `PaymentMethod::ALL`
+    Method(u32),
+    Amount(String),
+    Record,
+    /// the last recorded payment, whatever its date
+    CancelLast,
+}
+
+#[relm4::factory(pub)]
+impl FactoryComponent for Model {
+    type ParentWidget = adw::PreferencesGroup;
+    type Input = Input;
+    /// the payments changed
+    type Output = DynamicIndex;
+    type Init = Entry;
+    type CommandOutput = ();
+
+    view! {
+        #[root]
+        add = &adw::ExpanderRow {
+            set_title: &format!("Facture {} · {}", self.entry.invoice.number, self.entry.invoice.client.name),
+            #[watch] set_subtitle: &self.summary(),
+
+            add_suffix = &gtk::Image {
+                #[watch] set_visible: self.entry.remaining() <= Money::ZERO,
+                set_icon_name: Some("object-select-symbolic"),
+                set_tooltip: "Payée",
+                add_css_class: "success",
+            },
+
+            add_row = &adw::ActionRow {
+                set_title: "Règlements reçus",
+                #[watch] set_subtitle: &self.payments(),
+
+                add_suffix = &gtk::Button {
+                    set_icon_name: "edit-undo-symbolic",
+                    set_tooltip: "Annuler le dernier règlement enregistré",
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "flat",
+                    #[watch] set_sensitive: !self.entry.payments.is_empty(),
+                    connect_clicked => Input::CancelLast,
+                },
+            },
+
+            add_row = self.date_row.widget(),
+
+            add_row = &adw::ComboRow {
+                set_title: "Mode de règlement",
+                set_model: Some(&gtk::StringList::new(
+                    &PaymentMethod::ALL.map(|method| method.label())
+                )),
+                connect_selected_notify[sender] => move |row| {
+                    sender.input(Input::Method(row.selected()));
+                },
+            },
+
+            add_row = &adw::EntryRow {
+                set_title: "Montant (€)",
+                set_input_purpose: gtk::InputPurpose::Number,
+
+                #[track(self.reset_amount)]
+                #[block_signal(amount_handler)]
+                set_text: &self.amount,
+
+                #[watch] set_css_classes: if self.parsed_amount().is_none() { &["error"] } else { &[""] },
+
+                connect_changed[sender] => move |row| {
+                    sender.input(Input::Amount(row.text().to_string()));
+                } @amount_handler,
+            },
+
+            add_row = &adw::ButtonRow {
+                set_title: "Enregistrer le règlement",
+                #[watch] set_sensitive: self.parsed_amount().is_some(),
+                connect_activated => Input::Record,
+            },
+        }
+    }
+
+    fn init_model(
+        entry: Self::Init,
+        index: &DynamicIndex,
+        _sender: FactorySender<Self>,
+    ) -> Self {
+        let date_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Date du règlement".to_string(), date: Local::now().date_naive() })
+            .detach();
+        let amount = remaining_text(&entry);
+        Self {
+            entry,
+            method: PaymentMethod::default(),
+            amount,
+            reset_amount: true,
+            date_row,
+            index: index.clone(),
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+        widgets
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        self.reset_amount = false;
+        match message {
+            Input::Method(index) => {
+                self.method = PaymentMethod::ALL[index as usize];
+            },
+            Input::Amount(amount) => self.amount = amount,
+            Input::Record => {
+                let Some(amount) = self.parsed_amount() else { return };
+                self.entry.payments.push(Payment {
+                    date: self.date_row.model().date(),
+                    amount,
+                    method: self.method,
+                });
+                self.reset(&sender);
+            },
+            Input::CancelLast => {
+                self.entry.payments.pop();
+                self.reset(&sender);
+            },
+        }
+    }
+}
+
+impl Model {
+    fn parsed_amount(&self) -> Option<Money> {
+        self.amount.parse::<Money>().ok()
+            .filter(|amount| *amount > Money::ZERO)
+    }
+
+    /// payments were edited: suggest the new remaining amount and notify the parent
+    fn reset(&mut self, sender: &FactorySender<Self>) {
+        self.amount = remaining_text(&self.entry);
+        self.reset_amount = true;
+        sender.output(self.index.clone()).unwrap();
+    }
+
+    fn summary(&self) -> String {
+        let invoice = &self.entry.invoice;
+        let remaining = self.entry.remaining();
+        let status = if remaining <= Money::ZERO {
+            "payée".to_string()
+        } else if self.entry.payments.is_empty() {
+            format!("échéance le {}", invoice.due_date.format("%d/%m/%Y"))
+        } else {
+            format!("reste {remaining} €")
+        };
+        format!("{} · {} € · {status}", invoice.date.format("%d/%m/%Y"), invoice.amount_due())
+    }
+
+    fn payments(&self) -> String {
+        if self.entry.payments.is_empty() {
+            return "Aucun".to_string();
+        }
+        let mut payments: Vec<&Payment> = self.entry.payments.iter().collect();
+        payments.sort_by_key(|payment| payment.date);
+        payments.into_iter()
+            .map(|payment| format!("{} · {} · {} €",
+                payment.date.format("%d/%m/%Y"), payment.method.label(), payment.amount))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn remaining_text(entry: &Entry) -> String {
+    entry.remaining().max(Money::ZERO).to_string()
+}
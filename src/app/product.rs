@@ -1,20 +1,103 @@
 use std::fmt::{self, Display};
 
+use crate::app::ServiceDate;
+use crate::money::Money;
+
 #[derive(Debug,Default,Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Product {
     pub name: String,
     pub description: String,
-    pub quantity: u32,
-    pub price: f64,
-    pub total: f64,
+    pub quantity: f64,
+    #[serde(default)]
+    pub unit: Unit,
+    pub price: Money,
+    /// price times quantity, rounded to the cent
+    pub total: Money,
+    #[serde(default)]
+    pub service_date: Option<ServiceDate>,
+}
+
+/// what the quantity of a product counts
+#[derive(Debug,Default,Clone,PartialEq,Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum Unit {
+    /// plain number of items
+    #[default]
+    None,
+    Hour,
+    Day,
+    Page,
+    Word,
+    Forfait,
+    Custom(String),
+}
+
+impl Unit {
+    /// labels of the unit combo row, in the same order as `index`
+    pub(crate) const LABELS: [&'static str; 7] = ["Aucune", "Heure", "Jour", "Page", "Mot", "Forfait", "Personnalisée"];
+
+    pub(crate) fn index(&self) -> u32 {
+        match self {
+            Unit::None => 0,
+            Unit::Hour => 1,
+            Unit::Day => 2,
+            Unit::Page => 3,
+            Unit::Word => 4,
+            Unit::Forfait => 5,
+            Unit::Custom(_) => 6,
+        }
+    }
+
+    /// unit written after a quantity, plural from 2 as in french
+    pub fn name(&self, quantity: f64) -> String {
+        let singular = match self {
+            Unit::None => return String::new(),
+            Unit::Custom(name) => return name.clone(),
+            Unit::Hour => "heure",
+            Unit::Day => "jour",
+            Unit::Page => "page",
+            Unit::Word => "mot",
+            Unit::Forfait => "forfait",
+        };
+        if quantity.abs() >= 2.0 {
+            format!("{singular}s")
+        } else {
+            singular.to_string()
+        }
+    }
+
+    /// quantity followed by the unit, with a decimal comma
+    ///     -> 2,5 heures
+    pub fn with_quantity(&self, quantity: f64) -> String {
+        match self.name(quantity) {
+            unit if unit.is_empty() => format_quantity(quantity),
+            unit => format!("{} {unit}", format_quantity(quantity)),
+        }
+    }
+}
+
+impl Product {
+    /// quantity followed by its unit, with a decimal comma
+    ///     -> 2,5 heures
+    pub fn quantity_label(&self) -> String {
+        self.unit.with_quantity(self.quantity)
+    }
+}
+
+/// quantity with at most two decimals, without trailing zeros and with a decimal comma
+///     2.50 -> 2,5
+pub fn format_quantity(quantity: f64) -> String {
+    let formatted = format!("{:.2}", quantity);
+    formatted.trim_end_matches('0').trim_end_matches('.').replace('.', ",")
 }
 
 impl Display for Product {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} -> {} x {} € = {} €\n - {}",
             self.name,
-            self.quantity,
+            self.quantity_label(),
             self.price,
             self.total,
             self.description,
@@ -4,6 +4,7 @@ use relm4::prelude::*;
 mod edit_page;
 mod row;
 use crate::app::Product;
+use crate::money::Money;
 
 
 pub(crate) struct ProductsModel {
@@ -11,6 +12,8 @@ pub(crate) struct ProductsModel {
     edit: Controller<edit_page::Model>,
     current_index: Option<DynamicIndex>,
     show_edit: bool,
+    /// products are the recurring products of the client and weren't edited
+    from_defaults: bool,
 }
 
 
@@ -21,6 +24,8 @@ pub(crate) enum ProductsInput {
     Edit(DynamicIndex),
     Edited(Product),
     HandleEditPageClosingRequest,
+    /// recurring products of the picked client
+    ApplyDefaults(Vec<Product>),
 }
 
 #[relm4::component(pub(crate))]
@@ -45,8 +50,7 @@ impl SimpleComponent for ProductsModel {
                         set_title: &format!{"Total: {} €",
                             model.products.iter()
                                 .map(|row| row.product.total)
-                                .sum::<f64>()
-                                .abs() // avoid -0
+                                .sum::<Money>()
                         }.to_string(),
 
                         #[wrap(Some)]
@@ -103,6 +107,7 @@ impl SimpleComponent for ProductsModel {
             edit,
             current_index: None,
             show_edit: false,
+            from_defaults: false,
         };
 
         let products_box = model.products.widget();
@@ -113,11 +118,13 @@ impl SimpleComponent for ProductsModel {
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
         match message {
             ProductsInput::Add => {
+                self.from_defaults = false;
                 let mut products_guard = self.products.guard();
                 let index = products_guard.push_back(row::ProductInit{});
                 sender.input_sender().emit(ProductsInput::Edit(index));
             }
             ProductsInput::Delete(index) => {
+                self.from_defaults = false;
                 let mut products_guard = self.products.guard();
                 products_guard.remove(index.current_index());
             }
@@ -145,6 +152,7 @@ impl SimpleComponent for ProductsModel {
                 );
                 // TODO: use Edited message instead?
                 row.unwrap().product = product;
+                self.from_defaults = false;
 
                 self.current_index = None;
                 self.show_edit = false;
@@ -152,6 +160,17 @@ impl SimpleComponent for ProductsModel {
             ProductsInput::HandleEditPageClosingRequest => {
                 self.show_edit = false;
             }
+            ProductsInput::ApplyDefaults(products) => {
+                // don't discard products entered by hand
+                if !self.products.is_empty() && !self.from_defaults { return; }
+                let mut products_guard = self.products.guard();
+                products_guard.clear();
+                for product in products {
+                    let index = products_guard.push_back(row::ProductInit{});
+                    products_guard.get_mut(index.current_index()).unwrap().product = product;
+                }
+                self.from_defaults = true;
+            }
         }
         sender.output(self.get_products()).unwrap();
     }
@@ -1,21 +1,34 @@
 use adw::prelude::*;
+use chrono::{Local, NaiveDate};
 use gtk::InputPurpose;
 use relm4::prelude::*;
 
-use crate::app::Product;
+use crate::app::date_row::{DateRowInit, DateRowInput, DateRowModel};
+use crate::app::{Product, ServiceDate, Unit};
+use crate::money::Money;
 
-#[derive(Default,Clone)]
 pub struct Model {
     pub product: Product,
     pub editing: bool,
+    /// last custom unit entered, restored when switching back to `Unit::Custom`
+    custom_unit: String,
+    service_start_row: Controller<DateRowModel>,
+    service_end_row: Controller<DateRowModel>,
 }
 
 #[derive(Debug)]
 pub enum Input {
     NameChanged(String),
     DescriptionChanged(String),
-    QuantityChanged(u32),
-    PriceChanged(Result<f64,()>),
+    QuantityChanged(f64),
+    /// index in `Unit::LABELS`
+    UnitKind(u32),
+    CustomUnit(String),
+    PriceChanged(Result<Money,()>),
+    HasServiceDate(bool),
+    HasServiceEnd(bool),
+    ServiceStart(NaiveDate),
+    ServiceEnd(NaiveDate),
     Edit(Product),
     Shown,
     Hiding,
@@ -119,18 +132,51 @@ impl SimpleComponent for Model {
                             #[track(!model.editing)]
                             #[block_signal(quantity_handler)]
                             set_adjustment: Some(&gtk::Adjustment::builder()
-                                .lower(1.0)
-                                .upper(100.0)
-                                .value(model.product.quantity.into())
+                                .lower(0.0)
+                                .upper(1_000_000.0)
+                                .value(model.product.quantity)
                                 .step_increment(1.0)
                                 .page_increment(10.0)
                                 .build()),
+                            set_digits: 2,
+
+                            #[watch] set_class_active: ("error", model.product.quantity <= 0.0),
 
                             connect_changed[sender] => move |row| {
-                                sender.input(Input::QuantityChanged(row.value() as u32));
+                                sender.input(Input::QuantityChanged(row.value()));
                             } @quantity_handler,
                         },
 
+                        #[name(unit)]
+                        add = &adw::ComboRow {
+                            set_title: "Unité",
+                            set_model: Some(&gtk::StringList::new(&Unit::LABELS)),
+
+                            #[track(!model.editing)]
+                            #[block_signal(unit_handler)]
+                            set_selected: model.product.unit.index(),
+
+                            connect_selected_notify[sender] => move |row| {
+                                sender.input(Input::UnitKind(row.selected()));
+                            } @unit_handler,
+                        },
+
+                        #[name(custom_unit)]
+                        add = &adw::EntryRow {
+                            set_title: "Unité personnalisée",
+                            #[watch] set_visible: matches!(model.product.unit, Unit::Custom(_)),
+
+                            #[track(!model.editing)]
+                            #[block_signal(custom_unit_handler)]
+                            set_text: &model.custom_unit,
+
+                            #[watch] set_class_active: ("error", matches!(&model.product.unit, Unit::Custom(name) if name.is_empty())),
+
+                            connect_changed[sender] => move |row| {
+                                sender.input(Input::CustomUnit(row.text().to_string()));
+                            } @custom_unit_handler,
+                        },
+
                         #[name(price)]
                         add = &adw::ActionRow {
                             set_title: "Price",
@@ -159,7 +205,7 @@ impl SimpleComponent for Model {
                                     // TODO: improve handling so only numbers are possible to enter
                                     connect_text_notify[sender] => move |buffer| {
                                         sender.input(Input::PriceChanged(
-                                            match buffer.text().parse::<f64>() {
+                                            match buffer.text().parse::<Money>() {
                                                 Ok(value) => Ok(value),
                                                 Err(_value) => Err(()),
                                             }
@@ -187,6 +233,38 @@ impl SimpleComponent for Model {
                         },
                     },
 
+                    add = &adw::PreferencesGroup {
+                        #[name(service_date)]
+                        add = &adw::ExpanderRow {
+                            set_title: "Date de prestation",
+                            set_show_enable_switch: true,
+
+                            #[track(!model.editing)]
+                            #[block_signal(service_date_handler)]
+                            set_enable_expansion: model.product.service_date.is_some(),
+
+                            connect_enable_expansion_notify[sender] => move |row| {
+                                sender.input(Input::HasServiceDate(row.enables_expansion()));
+                            } @service_date_handler,
+
+                            add_row = model.service_start_row.widget(),
+
+                            add_row = &adw::SwitchRow {
+                                set_title: "Période",
+
+                                #[track(!model.editing)]
+                                #[block_signal(service_end_handler)]
+                                set_active: model.product.service_date.is_some_and(|date| date.end.is_some()),
+
+                                connect_active_notify[sender] => move |switch| {
+                                    sender.input(Input::HasServiceEnd(switch.is_active()));
+                                } @service_end_handler,
+                            },
+
+                            add_row = model.service_end_row.widget(),
+                        },
+                    },
+
                     add = &adw::PreferencesGroup {
                         #[name(validation_button)]
                         add = &gtk::Button {
@@ -220,23 +298,38 @@ impl SimpleComponent for Model {
             None => Product {
                 name: "".to_owned(),
                 description: "".to_owned(),
-                price: 0.0,
-                quantity: 1,
-                total: 0.0,
+                price: Money::ZERO,
+                quantity: 1.0,
+                unit: Unit::None,
+                total: Money::ZERO,
+                service_date: None,
             },
         };
+
+        let today = Local::now().date_naive();
+        let service_start_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Début".to_string(), date: today })
+            .forward(sender.input_sender(), Input::ServiceStart);
+        let service_end_row = DateRowModel::builder()
+            .launch(DateRowInit { title: "Fin".to_string(), date: today })
+            .forward(sender.input_sender(), Input::ServiceEnd);
+
+        let custom_unit = match &init_product.unit {
+            Unit::Custom(name) => name.clone(),
+            _ => String::new(),
+        };
         let model = Model {
             product: init_product,
             editing: false,
+            custom_unit,
+            service_start_row,
+            service_end_row,
         };
         let widgets = view_output!();
         ComponentParts { model, widgets }
     }
 
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
-        let total = self.product.price * (self.product.quantity as f64);
-        // make sure to use 2 decimal point precision
-        self.product.total = (100.0*total).round() / 100.0;
         match message {
             Input::NameChanged(value) => {
                 self.product.name = value;
@@ -247,11 +340,58 @@ impl SimpleComponent for Model {
             Input::QuantityChanged(value) => {
                 self.product.quantity = value;
             },
+            Input::UnitKind(index) => {
+                self.product.unit = match index {
+                    1 => Unit::Hour,
+                    2 => Unit::Day,
+                    3 => Unit::Page,
+                    4 => Unit::Word,
+                    5 => Unit::Forfait,
+                    6 => Unit::Custom(self.custom_unit.clone()),
+                    _ => Unit::None,
+                };
+            },
+            Input::CustomUnit(name) => {
+                self.custom_unit = name.clone();
+                if let Unit::Custom(_) = self.product.unit {
+                    self.product.unit = Unit::Custom(name);
+                }
+            },
             Input::PriceChanged(value) => {
                 if let Ok(value) = value { self.product.price = value };
             },
+            Input::HasServiceDate(value) => {
+                self.product.service_date = if value {
+                    Some(ServiceDate { start: self.service_start_row.model().date(), end: None })
+                } else {
+                    None
+                };
+            },
+            Input::HasServiceEnd(value) => {
+                let end = if value { Some(self.service_end_row.model().date()) } else { None };
+                if let Some(service_date) = &mut self.product.service_date {
+                    service_date.end = end;
+                }
+            },
+            Input::ServiceStart(date) => {
+                if let Some(service_date) = &mut self.product.service_date {
+                    service_date.start = date;
+                }
+            },
+            Input::ServiceEnd(date) => {
+                if let Some(service_date) = &mut self.product.service_date {
+                    service_date.end = Some(date);
+                }
+            },
             Input::Edit(product) => {
                 println!("editing {:?}", product);
+                if let Some(service_date) = product.service_date {
+                    self.service_start_row.emit(DateRowInput::Set(service_date.start));
+                    self.service_end_row.emit(DateRowInput::Set(service_date.end.unwrap_or(service_date.start)));
+                }
+                if let Unit::Custom(name) = &product.unit {
+                    self.custom_unit = name.clone();
+                }
                 self.product = product;
             },
             Input::Hiding => {
@@ -265,19 +405,36 @@ impl SimpleComponent for Model {
                 sender.output(Output::CloseRequest).unwrap();
             }
         }
+        // rounded to the cent for each line, invoice totals are sums of lines
+        self.product.total = self.product.price.times(self.product.quantity);
+        self.service_end_row.widget().set_sensitive(
+            self.product.service_date.is_some_and(|date| date.end.is_some())
+        );
+        self.service_end_row.widget().set_class_active(
+            "error",
+            self.product.service_date.is_some_and(|date| !date.valid()),
+        );
     }
 }
 
 impl Model {
     fn requirements_fullfilled(&self) -> bool {
-        if self.product.name.is_empty() {
+        if self.product.name.is_empty() || self.product.quantity <= 0.0 {
+            return false;
+        }
+        if let Unit::Custom(name) = &self.product.unit {
+            if name.is_empty() {
+                return false;
+            }
+        }
+        if self.product.service_date.is_some_and(|date| !date.valid()) {
             return false;
         }
         // Price is a valid number: only digits with a single . or ,
         // should be implemented at the source to prevent from entering any wrong character
         // right now it will always be valid but maybe wrong
 
-        // NB: Quantity is always a valid number by widget design
+        // NB: Quantity is always a number by widget design, but may be 0
 
         true
     }
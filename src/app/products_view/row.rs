@@ -2,7 +2,8 @@ use adw::prelude::*;
 use relm4::prelude::*;
 use relm4::factory::FactoryView;
 
-use crate::app::Product;
+use crate::app::{Product, Unit};
+use crate::money::Money;
 
 pub struct Model {
     pub product: Product,
@@ -64,7 +65,7 @@ impl FactoryComponent for Model {
                     #[watch]
                     set_label: &format!{ "{} x {}",
                         self.product.price,
-                        self.product.quantity
+                        self.product.quantity_label()
                     }.to_string(),
                 },
             },
@@ -119,9 +120,11 @@ impl FactoryComponent for Model {
             product: Product {
                 name: "".to_owned(),
                 description: "".to_owned(),
-                price: 0.0,
-                quantity: 1,
-                total: 0.0,
+                price: Money::ZERO,
+                quantity: 1.0,
+                unit: Unit::None,
+                total: Money::ZERO,
+                service_date: None,
             }
         }
     }
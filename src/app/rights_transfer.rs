@@ -0,0 +1,58 @@
+use crate::money::Money;
+
+/// cession de droits d'auteur attached to an invoice
+#[derive(Debug,Clone,PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RightsTransfer {
+    /// supports and modes of exploitation (print, web, exhibition...)
+    pub media: String,
+    pub territory: String,
+    pub duration: String,
+    pub exclusive: bool,
+    /// part of the invoice total paid for the transfer of rights, in %
+    pub rights_share: f64,
+}
+
+impl Default for RightsTransfer {
+    fn default() -> Self {
+        Self {
+            media: "".to_string(),
+            territory: "Monde entier".to_string(),
+            duration: "Durée légale de protection des droits d'auteur".to_string(),
+            exclusive: false,
+            rights_share: 100.0,
+        }
+    }
+}
+
+/// remuneration split between the work itself and the transfer of rights
+#[derive(Debug,Clone,Copy,PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct RemunerationSplit {
+    /// in %
+    pub work_share: f64,
+    pub work: Money,
+    /// in %
+    pub rights_share: f64,
+    pub rights: Money,
+}
+
+impl RightsTransfer {
+    pub fn valid(&self) -> bool {
+        !self.media.is_empty() &&
+        !self.territory.is_empty() &&
+        !self.duration.is_empty() &&
+        (0.0..=100.0).contains(&self.rights_share)
+    }
+
+    /// the work gets the remainder, so that both parts add up to the total
+    pub fn split(&self, total: Money) -> RemunerationSplit {
+        let rights = total.percent(self.rights_share);
+        RemunerationSplit {
+            work_share: 100.0 - self.rights_share,
+            work: total - rights,
+            rights_share: self.rights_share,
+            rights,
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+
+/// date of the sale or service (date de prestation)
+/// single day when there's no end, otherwise a period
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ServiceDate {
+    pub start: NaiveDate,
+    pub end: Option<NaiveDate>,
+}
+
+impl ServiceDate {
+    /// a period can't end before it starts
+    pub fn valid(&self) -> bool {
+        !self.end.is_some_and(|end| end < self.start)
+    }
+}
+
+impl fmt::Display for ServiceDate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "du {} au {}", self.start.format("%d/%m/%Y"), end.format("%d/%m/%Y")),
+            None => write!(f, "le {}", self.start.format("%d/%m/%Y")),
+        }
+    }
+}
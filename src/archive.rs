@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::app::{Client, ClientId};
+use crate::config::Config;
+use crate::latex::InvoiceData;
+use crate::money::Money;
+use crate::APP_NAME;
+
+
+/// issued invoices and devis, stored as json files in the app data directory
+pub struct Archive {
+    dir: PathBuf,
+}
+
+/// an archived invoice, with the payments received for it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub invoice: InvoiceData,
+    /// in the order they were recorded
+    #[serde(default)]
+    pub payments: Vec<Payment>,
+}
+
+impl Entry {
+    pub fn client_id(&self) -> ClientId {
+        self.invoice.client.id
+    }
+
+    pub fn paid(&self) -> Money {
+        self.payments.iter().map(|payment| payment.amount).sum()
+    }
+
+    pub fn remaining(&self) -> Money {
+        self.invoice.amount_due() - self.paid()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payment {
+    pub date: NaiveDate,
+    pub amount: Money,
+    pub method: PaymentMethod,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentMethod {
+    #[default]
+    Transfer,
+    Cheque,
+    Cash,
+    Card,
+    Other,
+}
+
+impl PaymentMethod {
+    /// in the same order as the payment method combo rows
+    pub const ALL: [PaymentMethod; 5] = [
+        PaymentMethod::Transfer,
+        PaymentMethod::Cheque,
+        PaymentMethod::Cash,
+        PaymentMethod::Card,
+        PaymentMethod::Other,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentMethod::Transfer => "Virement",
+            PaymentMethod::Cheque => "Chèque",
+            PaymentMethod::Cash => "Espèces",
+            PaymentMethod::Card => "Carte bancaire",
+            PaymentMethod::Other => "Autre",
+        }
+    }
+}
+
+impl Archive {
+    pub fn open() -> Result<Archive, Box<dyn std::error::Error>> {
+        let dir = Config::data_dir(APP_NAME).join("archive");
+        fs::create_dir_all(&dir)?;
+        Ok(Archive { dir })
+    }
+
+    /// store invoice, replacing any previous version with the same type and number
+    ///
+    /// payments already received for the previous version are kept
+    pub fn store(&self, invoice: &InvoiceData) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = self.dir.join(file_name(invoice));
+        let payments = match path.exists() {
+            true => read_entry(&path)?.payments,
+            false => Vec::new(),
+        };
+        write_entry(&path, &Entry { invoice: invoice.clone(), payments })?;
+        Ok(path)
+    }
+
+    /// replace the payments received for an archived invoice
+    pub fn set_payments(&self, invoice: &InvoiceData, payments: Vec<Payment>) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.dir.join(file_name(invoice));
+        let mut entry = read_entry(&path)?;
+        entry.payments = payments;
+        write_entry(&path, &entry)
+    }
+
+    /// all archived entries, sorted by date
+    pub fn entries(&self) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+        let mut entries: Vec<Entry> = self.read_all()?.into_iter().map(|(_, entry)| entry).collect();
+        entries.sort_by_key(|entry| entry.invoice.date);
+        Ok(entries)
+    }
+
+    /// archived entries of a client, sorted by date
+    pub fn entries_of(&self, client: ClientId) -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+        Ok(self.entries()?.into_iter().filter(|entry| entry.client_id() == client).collect())
+    }
+
+    /// invoices archived before clients had an id are linked to the client with the same name
+    ///
+    /// names shared by several clients are ambiguous, those invoices stay unlinked
+    pub fn link_clients(&self, clients: &HashMap<ClientId, Client>) -> Result<(), Box<dyn std::error::Error>> {
+        for (path, mut entry) in self.read_all()? {
+            if !entry.invoice.client.id.is_nil() { continue; }
+            let mut matching = clients.values().filter(|client| client.name == entry.invoice.client.name);
+            if let (Some(client), None) = (matching.next(), matching.next()) {
+                entry.invoice.client.id = client.id;
+                write_entry(&path, &entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// move the history of a merged duplicate to the client it was merged into
+    ///
+    /// the client details printed on the invoices are kept as issued
+    pub fn reassign_client(&self, from: ClientId, to: ClientId) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut count = 0;
+        for (path, mut entry) in self.read_all()? {
+            if entry.client_id() != from { continue; }
+            entry.invoice.client.id = to;
+            write_entry(&path, &entry)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// entries that can't be read are skipped, so that one damaged file doesn't hide the others
+    fn read_all(&self) -> Result<Vec<(PathBuf, Entry)>, Box<dyn std::error::Error>> {
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.dir)? {
+            let path = file?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                match read_entry(&path) {
+                    Ok(entry) => entries.push((path, entry)),
+                    Err(e) => println!("Skipping archived invoice {}: {e}", path.display()),
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn read_entry(path: &Path) -> Result<Entry, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_reader(File::open(path)?)?)
+}
+
+fn write_entry(path: &Path, entry: &Entry) -> Result<(), Box<dyn std::error::Error>> {
+    serde_json::to_writer_pretty(File::create(path)?, entry)?;
+    Ok(())
+}
+
+fn file_name(invoice: &InvoiceData) -> String {
+    let bill_type = if invoice.is_devis { "devis" } else { "facture" };
+    // invoice numbers may contain path separators
+    let number = invoice.number.replace(['/', '\\'], "-");
+    format!("{bill_type}-{number}.json")
+}
@@ -1,8 +1,18 @@
-use crate::app::{Author, Client, ClientName};
-use serde::Deserialize;
-use serde::Serialize;
+use crate::app::{Author, Client, ClientId};
+use crate::archive::Archive;
+use crate::contributions;
+use crate::dispenses::Dispenses;
+use crate::fec;
+use crate::money::Money;
+use crate::files;
+use crate::stats;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use chrono::{Datelike, Local};
+
+/// number of recent clients listed first in the client picker
+const RECENT_CLIENTS: usize = 10;
 
 // TODO: add last_devis
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,9 +22,22 @@ pub struct Config {
     pub author: Option<Author>,
     pub tex_output_path: String,
     pub pdf_output_path: String,
-    pub clients: HashMap<ClientName, Client>,
+    #[serde(deserialize_with = "deserialize_clients")]
+    pub clients: HashMap<ClientId, Client>,
     pub last_facture: Option<String>,
+    /// replaced by `dispenses`, only read to migrate older configs
+    #[serde(skip_serializing)]
     pub last_dispense: Option<PathBuf>,
+    /// dispenses de précompte by validity year
+    pub dispenses: Dispenses,
+    /// artist-author contribution rates, by first year of application
+    pub contribution_rates: Vec<contributions::Rates>,
+    /// last invoiced clients, most recent first
+    pub recent_clients: Vec<ClientId>,
+    /// account numbers of the FEC export
+    pub accounts: fec::Accounts,
+    /// revenue ceilings shown on the dashboard, by first year of application
+    pub thresholds: Vec<stats::Thresholds>,
 }
 
 /// `MyConfig` implements `Default`
@@ -28,6 +51,14 @@ impl ::std::default::Default for Config {
             clients: HashMap::new(),
             last_facture: None,
             last_dispense: None,
+            dispenses: Dispenses::default(),
+            contribution_rates: vec![contributions::Rates::default()],
+            recent_clients: Vec::new(),
+            accounts: fec::Accounts::default(),
+            thresholds: vec![
+                stats::Thresholds::default(),
+                stats::Thresholds { year: 2025, micro_entreprise: Money::from_cents(7_770_000), vat_franchise: Money::from_cents(3_750_000) },
+            ],
         }
     }
 }
@@ -35,26 +66,90 @@ impl ::std::default::Default for Config {
 impl Config {
     pub fn load_with_check(app_name: &str) -> Result<Config, Box<dyn std::error::Error>> {
         let mut cfg: Config = confy::load(app_name, None)?;
-        if let Some(dispense) = &cfg.last_dispense {
-            if !dispense.exists() {
-                cfg.last_dispense = None;
+        // the validity year of the dispense wasn't stored, assume it's the current one
+        if let Some(dispense) = cfg.last_dispense.take() {
+            let year = Local::now().year();
+            if cfg.dispenses.for_year(year).is_none() {
+                cfg.dispenses.set(year, dispense);
+            }
+        }
+
+        // files picked before they were copied into the data directory
+        for dispense in cfg.dispenses.iter_mut() {
+            if dispense.path.exists() {
+                dispense.path = files::import_or_keep(dispense.path.clone());
+            }
+        }
+        if let Some(author) = &mut cfg.author {
+            if let Some(signature) = author.signature_file_name.clone().map(PathBuf::from) {
+                if signature.exists() {
+                    author.signature_file_name = files::import_or_keep(signature).to_str().map(String::from);
+                }
             }
         }
 
+        // clients of older configs get their id in `deserialize_clients`,
+        // archived invoices need to reference them as well
+        // the archive isn't needed to start, failing to migrate it shouldn't prevent it
+        if let Err(e) = Archive::open().and_then(|archive| archive.link_clients(&cfg.clients)) {
+            println!("Couldn't link archived invoices to clients: {e}");
+        }
+
+        // persist migrations
+        confy::store(app_name, None, cfg.clone())?;
+
         Ok(cfg)
     }
 
+    /// directory where the app stores its data (archive, imported files...)
+    pub fn data_dir(app_name: &str) -> PathBuf {
+        // same qualifier as confy so both directories are named alike
+        directories::ProjectDirs::from("rs", "", app_name)
+            .expect("home directory should exist")
+            .data_dir()
+            .to_path_buf()
+    }
+
+    pub fn contribution_rates(&self, year: i32) -> contributions::Rates {
+        contributions::Rates::for_year(&self.contribution_rates, year)
+    }
+
+    pub fn thresholds(&self, year: i32) -> stats::Thresholds {
+        stats::Thresholds::for_year(&self.thresholds, year)
+    }
+
+    /// move client to the front of the recent clients
+    pub fn mark_recent(&mut self, client: ClientId) {
+        self.recent_clients.retain(|id| *id != client);
+        self.recent_clients.insert(0, client);
+        self.recent_clients.truncate(RECENT_CLIENTS);
+    }
+
     pub fn set_clients(&mut self, client_list: Vec<Client>) {
         self.clients = {
             let mut map = HashMap::new();
             for client in client_list {
-                map.insert(client.name.clone(), client);
+                map.insert(client.id, client);
             }
             map
         };
     }
 }
 
+/// clients used to be keyed by name, key them by id whatever the stored key
+/// and give an id to clients that have none
+fn deserialize_clients<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<ClientId, Client>, D::Error> {
+    let clients = HashMap::<String, Client>::deserialize(deserializer)?;
+    Ok(clients.into_values()
+        .map(|mut client| {
+            if client.id.is_nil() {
+                client.id = ClientId::new_v4();
+            }
+            (client.id, client)
+        })
+        .collect())
+}
+
 // fn vec_to_map(
 //     client_list: Vec<Client>,
 // ) -> std::collections::HashMap<std::string::String, app::client_form::Client> {
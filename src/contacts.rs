@@ -0,0 +1,191 @@
+use crate::app::Client;
+use crate::fuzzy;
+
+pub mod csv;
+pub mod vcard;
+
+
+/// client field a csv column is read into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientField {
+    Ignored,
+    Name,
+    NumberAndStreet,
+    Postcode,
+    City,
+    Country,
+    Contact,
+    Email,
+    Phone,
+    Siret,
+    Ape,
+    Tva,
+    TvaIcc,
+    Custom,
+}
+
+impl ClientField {
+    /// in the same order as the labels of the mapping combo rows
+    pub const ALL: [ClientField; 14] = [
+        ClientField::Ignored,
+        ClientField::Name,
+        ClientField::NumberAndStreet,
+        ClientField::Postcode,
+        ClientField::City,
+        ClientField::Country,
+        ClientField::Contact,
+        ClientField::Email,
+        ClientField::Phone,
+        ClientField::Siret,
+        ClientField::Ape,
+        ClientField::Tva,
+        ClientField::TvaIcc,
+        ClientField::Custom,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClientField::Ignored => "Ignorée",
+            ClientField::Name => "Nom",
+            ClientField::NumberAndStreet => "Adresse",
+            ClientField::Postcode => "Code postal",
+            ClientField::City => "Ville",
+            ClientField::Country => "Pays",
+            ClientField::Contact => "Interlocuteur",
+            ClientField::Email => "Mail",
+            ClientField::Phone => "Téléphone",
+            ClientField::Siret => "SIRET",
+            ClientField::Ape => "Code APE",
+            ClientField::Tva => "TVA",
+            ClientField::TvaIcc => "TVA intracommunautaire",
+            ClientField::Custom => "Informations additionnelles",
+        }
+    }
+
+    /// field matching a column header, in french or english
+    pub fn guess(header: &str) -> ClientField {
+        let header = fuzzy::normalize(header);
+        // whole words only, "vat" must not match "private"
+        let words: Vec<&str> = header.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+        let has = |candidates: &[&str]| candidates.iter().any(|candidate| words.contains(candidate));
+        // most specific first: "code postal" before "code", "tva icc" before "tva"
+        if has(&["siret", "siren"]) { return ClientField::Siret; }
+        if has(&["ape", "naf"]) { return ClientField::Ape; }
+        if has(&["intracom", "intracommunautaire", "icc", "vat"]) { return ClientField::TvaIcc; }
+        if has(&["tva"]) { return ClientField::Tva; }
+        if has(&["postal", "postcode", "zip", "zipcode", "cp"]) { return ClientField::Postcode; }
+        if has(&["ville", "city", "localite"]) { return ClientField::City; }
+        if has(&["pays", "country"]) { return ClientField::Country; }
+        if has(&["mail", "email", "courriel"]) { return ClientField::Email; }
+        if has(&["tel", "telephone", "phone", "portable", "mobile"]) { return ClientField::Phone; }
+        if has(&["adresse", "address", "rue", "street"]) { return ClientField::NumberAndStreet; }
+        if has(&["contact", "interlocuteur"]) { return ClientField::Contact; }
+        if has(&["note", "notes", "info", "infos", "informations", "comment", "comments", "commentaire", "commentaires"]) { return ClientField::Custom; }
+        if has(&["nom", "name", "societe", "company", "organisation", "organization", "raison"]) { return ClientField::Name; }
+        ClientField::Ignored
+    }
+
+    pub fn get(&self, client: &Client) -> String {
+        let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+        match self {
+            ClientField::Ignored => String::new(),
+            ClientField::Name => client.name.clone(),
+            ClientField::NumberAndStreet => client.address.number_and_street.clone(),
+            ClientField::Postcode => client.address.postcode.clone(),
+            ClientField::City => client.address.city.clone(),
+            ClientField::Country => client.address.country.clone(),
+            ClientField::Contact => optional(&client.contact),
+            ClientField::Email => optional(&client.email),
+            ClientField::Phone => optional(&client.phone),
+            ClientField::Siret => optional(&client.siret),
+            ClientField::Ape => optional(&client.code_ape),
+            ClientField::Tva => optional(&client.tva),
+            ClientField::TvaIcc => optional(&client.tva_icc),
+            ClientField::Custom => optional(&client.custom_field),
+        }
+    }
+
+    pub fn set(&self, client: &mut Client, value: &str) {
+        let value = value.trim().to_string();
+        let optional = if value.is_empty() { None } else { Some(value.clone()) };
+        match self {
+            ClientField::Ignored => (),
+            ClientField::Name => client.name = value,
+            ClientField::NumberAndStreet => client.address.number_and_street = value,
+            ClientField::Postcode => client.address.postcode = value,
+            ClientField::City => client.address.city = value,
+            ClientField::Country => client.address.country = value,
+            ClientField::Contact => client.contact = optional,
+            ClientField::Email => client.email = optional,
+            ClientField::Phone => client.phone = optional,
+            ClientField::Siret => client.siret = optional,
+            ClientField::Ape => client.code_ape = optional,
+            ClientField::Tva => client.tva = optional,
+            ClientField::TvaIcc => client.tva_icc = optional,
+            ClientField::Custom => client.custom_field = optional,
+        }
+    }
+}
+
+/// client of the list which is probably the same as `client`: same SIRET, or same name
+pub fn find_duplicate<'a>(client: &Client, clients: &'a [Client]) -> Option<&'a Client> {
+    let siret = |client: &Client| client.siret.as_ref()
+        .map(|siret| siret.chars().filter(|c| !c.is_whitespace()).collect::<String>());
+
+    if let Some(imported) = siret(client) {
+        if let Some(duplicate) = clients.iter().find(|existing| siret(existing).as_ref() == Some(&imported)) {
+            return Some(duplicate);
+        }
+    }
+    let name = fuzzy::normalize(client.name.trim());
+    clients.iter().find(|existing| fuzzy::normalize(existing.name.trim()) == name)
+}
+
+/// fill the empty fields of `existing` with the imported ones, the id and name are kept
+pub fn merge(existing: &mut Client, imported: &Client) {
+    for field in ClientField::ALL {
+        if field == ClientField::Name { continue; }
+        if field.get(existing).is_empty() {
+            field.set(existing, &field.get(imported));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guess_french_and_english_headers() {
+        assert_eq!(ClientField::guess("Raison sociale"), ClientField::Name);
+        assert_eq!(ClientField::guess("Company"), ClientField::Name);
+        assert_eq!(ClientField::guess("Code postal"), ClientField::Postcode);
+        assert_eq!(ClientField::guess("ZIP"), ClientField::Postcode);
+        assert_eq!(ClientField::guess("E-mail"), ClientField::Email);
+        assert_eq!(ClientField::guess("Téléphone portable"), ClientField::Phone);
+        assert_eq!(ClientField::guess("N° SIRET"), ClientField::Siret);
+        assert_eq!(ClientField::guess("Numéro de TVA intracommunautaire"), ClientField::TvaIcc);
+        assert_eq!(ClientField::guess("VAT number"), ClientField::TvaIcc);
+        assert_eq!(ClientField::guess("TVA"), ClientField::Tva);
+        assert_eq!(ClientField::guess("Nom du contact"), ClientField::Contact);
+        assert_eq!(ClientField::guess("Identifiant"), ClientField::Ignored);
+    }
+
+    #[test]
+    fn guess_whole_words_only() {
+        // "vat" in "private", "cp" in "ecp", "tel" in "hotel", "ape" in "paper"
+        assert_eq!(ClientField::guess("Private notes"), ClientField::Custom);
+        assert_eq!(ClientField::guess("ECP"), ClientField::Ignored);
+        assert_eq!(ClientField::guess("Hotel"), ClientField::Ignored);
+        assert_eq!(ClientField::guess("Paper"), ClientField::Ignored);
+        assert_eq!(ClientField::guess("Renommée"), ClientField::Ignored);
+    }
+
+    #[test]
+    fn labels_are_guessed_back() {
+        for field in ClientField::ALL {
+            if field == ClientField::Ignored { continue; }
+            assert_eq!(ClientField::guess(field.label()), field, "{}", field.label());
+        }
+    }
+}
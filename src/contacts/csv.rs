@@ -0,0 +1,128 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use crate::app::Client;
+use crate::contacts::ClientField;
+
+
+/// rows of a csv file, before its columns are mapped to client fields
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// client field of each column, from its header
+    pub fn guess_mapping(&self) -> Vec<ClientField> {
+        self.headers.iter().map(|header| ClientField::guess(header)).collect()
+    }
+
+    /// clients of the rows with a name
+    pub fn clients(&self, mapping: &[ClientField]) -> Vec<Client> {
+        self.rows.iter()
+            .map(|row| {
+                let mut client = Client::new();
+                for (value, field) in row.iter().zip(mapping) {
+                    field.set(&mut client, value);
+                }
+                client
+            })
+            .filter(|client| !client.name.is_empty())
+            .collect()
+    }
+}
+
+/// exported columns, named so that they are guessed back on import
+const COLUMNS: [ClientField; 13] = [
+    ClientField::Name,
+    ClientField::NumberAndStreet,
+    ClientField::Postcode,
+    ClientField::City,
+    ClientField::Country,
+    ClientField::Contact,
+    ClientField::Email,
+    ClientField::Phone,
+    ClientField::Siret,
+    ClientField::Ape,
+    ClientField::Tva,
+    ClientField::TvaIcc,
+    ClientField::Custom,
+];
+
+pub fn write_file(path: &Path, clients: &[Client]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = ::csv::Writer::from_path(path)?;
+    writer.write_record(COLUMNS.map(|field| field.label()))?;
+    for client in clients {
+        writer.write_record(COLUMNS.map(|field| field.get(client)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn read(path: &Path) -> Result<Table, Box<dyn std::error::Error>> {
+    parse(&read_to_string(path)?)
+}
+
+/// table of a csv `content` separated by commas or semicolons
+pub fn parse(content: &str) -> Result<Table, Box<dyn std::error::Error>> {
+    // spreadsheets exported with a french locale use semicolons
+    let first_line = content.lines().next().unwrap_or_default();
+    let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() { b';' } else { b',' };
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers = reader.headers()?.iter().map(String::from).collect();
+    let rows = reader.records()
+        .map(|record| record.map(|record| record.iter().map(String::from).collect()))
+        .collect::<Result<_, _>>()?;
+    Ok(Table { headers, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commas() {
+        let table = parse("Nom,Ville\nDupont,Paris\n").unwrap();
+        assert_eq!(table.headers, ["Nom", "Ville"]);
+        assert_eq!(table.rows, [["Dupont", "Paris"]]);
+    }
+
+    #[test]
+    fn semicolons() {
+        let table = parse("Nom;Adresse;Ville\nDupont;\"1, rue de la Paix\";Paris\n").unwrap();
+        assert_eq!(table.headers, ["Nom", "Adresse", "Ville"]);
+        assert_eq!(table.rows, [["Dupont", "1, rue de la Paix", "Paris"]]);
+    }
+
+    #[test]
+    fn byte_order_mark_and_short_rows() {
+        let table = parse("\u{feff}Nom;Ville\nDupont\n").unwrap();
+        assert_eq!(table.headers, ["Nom", "Ville"]);
+        assert_eq!(table.rows, [vec!["Dupont"]]);
+    }
+
+    #[test]
+    fn clients_of_the_mapped_rows() {
+        let table = parse("Raison sociale;Code postal;Private notes\nDupont;75001;client fidèle\n;13000;\n").unwrap();
+        let mapping = table.guess_mapping();
+        assert_eq!(mapping, [ClientField::Name, ClientField::Postcode, ClientField::Custom]);
+        let clients = table.clients(&mapping);
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].name, "Dupont");
+        assert_eq!(clients[0].address.postcode, "75001");
+        assert_eq!(clients[0].custom_field.as_deref(), Some("client fidèle"));
+        assert_eq!(clients[0].tva_icc, None);
+    }
+
+    #[test]
+    fn exported_columns_are_guessed_back() {
+        let headers = COLUMNS.map(|field| field.label().to_string()).join(",");
+        let table = parse(&headers).unwrap();
+        assert_eq!(table.guess_mapping(), COLUMNS);
+    }
+}
@@ -0,0 +1,326 @@
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+use crate::app::Client;
+
+
+/// clients of a vcard 3 or 4 file
+pub fn read(path: &Path) -> Result<Vec<Client>, Box<dyn std::error::Error>> {
+    Ok(parse(&read_to_string(path)?))
+}
+
+pub fn write_file(path: &Path, clients: &[Client]) -> Result<(), Box<dyn std::error::Error>> {
+    write(path, to_string(clients))?;
+    Ok(())
+}
+
+/// vcard 4 of each client, the client name is the organization
+pub fn to_string(clients: &[Client]) -> String {
+    let mut content = String::new();
+    for client in clients {
+        let mut properties = vec![
+            "BEGIN:VCARD".to_string(),
+            "VERSION:4.0".to_string(),
+            "KIND:org".to_string(),
+            format!("UID:urn:uuid:{}", client.id),
+            format!("FN:{}", escape(client.contact.as_ref().unwrap_or(&client.name))),
+            format!("ORG:{}", escape(&client.name)),
+            format!("ADR;TYPE=work:;;{};{};;{};{}",
+                escape(&client.address.number_and_street),
+                escape(&client.address.city),
+                escape(&client.address.postcode),
+                escape(&client.address.country),
+            ),
+        ];
+        let optional = [
+            ("EMAIL", &client.email),
+            ("TEL", &client.phone),
+            ("NOTE", &client.custom_field),
+            ("X-SIRET", &client.siret),
+            ("X-APE", &client.code_ape),
+            ("X-TVA", &client.tva),
+            ("X-TVA-ICC", &client.tva_icc),
+        ];
+        for (name, value) in optional {
+            if let Some(value) = value {
+                properties.push(format!("{name}:{}", escape(value)));
+            }
+        }
+        properties.push("END:VCARD".to_string());
+
+        for property in properties {
+            content.push_str(&fold(&property));
+        }
+    }
+    content
+}
+
+/// clients of the vcards of `content`, organizations are named after the organization
+/// and the person becomes the contact
+pub fn parse(content: &str) -> Vec<Client> {
+    let mut clients = Vec::new();
+    let mut card: Option<Card> = None;
+
+    for line in unfold(content) {
+        let Some((name, value)) = split_property(&line) else { continue };
+        if name == "BEGIN" && value.eq_ignore_ascii_case("VCARD") {
+            card = Some(Card::default());
+        } else if name == "END" {
+            if let Some(client) = card.take().and_then(Card::into_client) {
+                clients.push(client);
+            }
+        } else if let Some(card) = &mut card {
+            card.read(&name, value);
+        }
+    }
+    clients
+}
+
+/// properties of a vcard used for clients, only the first of each is kept
+#[derive(Default)]
+struct Card {
+    full_name: Option<String>,
+    organization: Option<String>,
+    address: Option<Vec<String>>,
+    email: Option<String>,
+    phone: Option<String>,
+    note: Option<String>,
+    siret: Option<String>,
+    code_ape: Option<String>,
+    tva: Option<String>,
+    tva_icc: Option<String>,
+}
+
+impl Card {
+    fn read(&mut self, name: &str, value: &str) {
+        let field = match name {
+            "FN" => &mut self.full_name,
+            "ORG" => {
+                // organization units follow the name
+                if self.organization.is_none() {
+                    self.organization = components(value).into_iter().next();
+                }
+                return;
+            },
+            "ADR" => {
+                if self.address.is_none() {
+                    self.address = Some(components(value));
+                }
+                return;
+            },
+            "EMAIL" => &mut self.email,
+            "TEL" => &mut self.phone,
+            "NOTE" => &mut self.note,
+            "X-SIRET" => &mut self.siret,
+            "X-APE" => &mut self.code_ape,
+            "X-TVA" => &mut self.tva,
+            "X-TVA-ICC" => &mut self.tva_icc,
+            _ => return,
+        };
+        if field.is_none() {
+            let value = unescape(value);
+            if !value.trim().is_empty() {
+                *field = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    fn into_client(self) -> Option<Client> {
+        let mut client = Client::new();
+        match (self.organization.filter(|org| !org.is_empty()), self.full_name) {
+            (Some(organization), full_name) => {
+                // exported organizations without contact have the same full name
+                client.contact = full_name.filter(|full_name| *full_name != organization);
+                client.name = organization;
+            },
+            (None, Some(full_name)) => client.name = full_name,
+            (None, None) => return None,
+        }
+        // post office box, extended address, street, locality, region, postal code, country
+        if let Some(address) = self.address {
+            let component = |i: usize| address.get(i).cloned().unwrap_or_default();
+            client.address.number_and_street = [component(1), component(2)]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(", ");
+            client.address.city = component(3);
+            client.address.postcode = component(5);
+            client.address.country = component(6);
+        }
+        client.email = self.email.map(|email| email.trim_start_matches("mailto:").to_string());
+        client.phone = self.phone.map(|phone| phone.trim_start_matches("tel:").to_string());
+        client.custom_field = self.note;
+        client.siret = self.siret;
+        client.code_ape = self.code_ape;
+        client.tva = self.tva;
+        client.tva_icc = self.tva_icc;
+        Some(client)
+    }
+}
+
+/// lines of the file, with folded lines joined back
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// uppercase property name without group nor parameters, and raw value
+fn split_property(line: &str) -> Option<(String, &str)> {
+    // parameter values may be quoted and contain colons
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' { quoted = !quoted; }
+        c == ':' && !quoted
+    })?.0;
+    let name = line[..colon].split(';').next()?;
+    let name = name.rsplit('.').next()?;
+    Some((name.to_uppercase(), &line[colon + 1..]))
+}
+
+/// components of a structured value, separated by unescaped semicolons
+fn components(value: &str) -> Vec<String> {
+    let mut components = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => components.last_mut().unwrap().push('\n'),
+                Some(escaped) => components.last_mut().unwrap().push(escaped),
+                None => (),
+            },
+            ';' => components.push(String::new()),
+            c => components.last_mut().unwrap().push(c),
+        }
+    }
+    components.into_iter().map(|component| component.trim().to_string()).collect()
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => (),
+        }
+    }
+    unescaped
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// line of at most 75 bytes, continued on the following lines
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> Client {
+        let mut client = Client::new();
+        client.name = "Dupont, Durand; et associés".to_string();
+        client.contact = Some("Jeanne Dupont".to_string());
+        client.address.number_and_street = "12, rue de la Paix".to_string();
+        client.address.postcode = "75002".to_string();
+        client.address.city = "Paris".to_string();
+        client.address.country = "France".to_string();
+        client.email = Some("jeanne@dupont.fr".to_string());
+        client.phone = Some("01 23 45 67 89".to_string());
+        client.custom_field = Some("première ligne\nseconde ligne, bien plus longue que la largeur d'une ligne de vcard".to_string());
+        client.siret = Some("404 833 048 00022".to_string());
+        client.code_ape = Some("9001Z".to_string());
+        client.tva = Some("FR83404833048".to_string());
+        client.tva_icc = Some("FR83404833048".to_string());
+        client
+    }
+
+    #[test]
+    fn round_trip() {
+        let client = client();
+        let content = to_string(std::slice::from_ref(&client));
+        assert!(content.lines().all(|line| line.len() <= 75));
+        assert!(content.contains("\r\n "));
+
+        let clients = parse(&content);
+        assert_eq!(clients.len(), 1);
+        let parsed = &clients[0];
+        assert_eq!(parsed.name, client.name);
+        assert_eq!(parsed.contact, client.contact);
+        assert_eq!(parsed.address.number_and_street, client.address.number_and_street);
+        assert_eq!(parsed.address.postcode, client.address.postcode);
+        assert_eq!(parsed.address.city, client.address.city);
+        assert_eq!(parsed.address.country, client.address.country);
+        assert_eq!(parsed.email, client.email);
+        assert_eq!(parsed.phone, client.phone);
+        assert_eq!(parsed.custom_field, client.custom_field);
+        assert_eq!(parsed.siret, client.siret);
+        assert_eq!(parsed.code_ape, client.code_ape);
+        assert_eq!(parsed.tva, client.tva);
+        assert_eq!(parsed.tva_icc, client.tva_icc);
+    }
+
+    #[test]
+    fn organization_without_contact() {
+        let mut client = client();
+        client.contact = None;
+        let clients = parse(&to_string(&[client]));
+        assert_eq!(clients[0].contact, None);
+    }
+
+    #[test]
+    fn vcard_3_person() {
+        let content = "BEGIN:VCARD\n\
+            VERSION:3.0\n\
+            item1.FN:Jeanne\n  Dupont\n\
+            TEL;TYPE=\"work,voice\":tel:0123456789\n\
+            EMAIL;TYPE=INTERNET:mailto:jeanne@dupont.fr\n\
+            ADR:;Bâtiment B;1 rue de la Paix;Paris;;75002;\n\
+            NOTE:a\\, b\\; c\\nd\n\
+            END:VCARD\n\
+            BEGIN:VCARD\n\
+            VERSION:3.0\n\
+            END:VCARD\n";
+        let clients = parse(content);
+        assert_eq!(clients.len(), 1);
+        let client = &clients[0];
+        assert_eq!(client.name, "Jeanne Dupont");
+        assert_eq!(client.contact, None);
+        assert_eq!(client.phone.as_deref(), Some("0123456789"));
+        assert_eq!(client.email.as_deref(), Some("jeanne@dupont.fr"));
+        assert_eq!(client.address.number_and_street, "Bâtiment B, 1 rue de la Paix");
+        assert_eq!(client.address.city, "Paris");
+        assert_eq!(client.address.postcode, "75002");
+        assert_eq!(client.custom_field.as_deref(), Some("a, b; c\nd"));
+    }
+}
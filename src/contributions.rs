@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use crate::app::Product;
+use crate::money::Money;
+
+
+/// artist-author contribution rates of a given year, in %
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Rates {
+    /// first year these rates apply to, until the next entry
+    pub year: i32,
+    /// contribution of the diffuseur to the artist-author social security
+    pub social_security: f64,
+    /// contribution of the diffuseur to professional training
+    pub professional_training: f64,
+    /// share of the gross amount CSG and CRDS are computed on
+    pub csg_crds_base: f64,
+    /// withheld when there's no dispense de précompte
+    pub csg: f64,
+    /// withheld when there's no dispense de précompte
+    pub crds: f64,
+    /// withheld when there's no dispense de précompte
+    pub vieillesse_plafonnee: f64,
+    /// withheld when there's no dispense de précompte
+    pub vieillesse_deplafonnee: f64,
+}
+
+impl Default for Rates {
+    fn default() -> Self {
+        Self {
+            year: 2019,
+            social_security: 1.0,
+            professional_training: 0.1,
+            csg_crds_base: 98.25,
+            csg: 9.2,
+            crds: 0.5,
+            vieillesse_plafonnee: 6.9,
+            vieillesse_deplafonnee: 0.4,
+        }
+    }
+}
+
+impl Rates {
+    /// rates of the most recent entry starting before or during `year`
+    pub fn for_year(rates: &[Rates], year: i32) -> Rates {
+        rates.iter()
+            .filter(|rates| rates.year <= year)
+            .max_by_key(|rates| rates.year)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// contributions due by the diffuseur (client) on an invoice
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DiffuseurContributions {
+    /// rates used for the computation
+    pub rates: Rates,
+    /// invoice total the contributions are computed on
+    pub base: Money,
+    pub social_security: Money,
+    pub professional_training: Money,
+    pub total: Money,
+}
+
+impl DiffuseurContributions {
+    pub fn compute(products: &[Product], rates: Rates) -> Self {
+        let base: Money = products.iter().map(|product| product.total).sum();
+        let social_security = base.percent(rates.social_security);
+        let professional_training = base.percent(rates.professional_training);
+
+        Self {
+            rates,
+            base,
+            social_security,
+            professional_training,
+            total: social_security + professional_training,
+        }
+    }
+}
+
+/// artist-author contributions withheld by the diffuseur (précompte)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Withholding {
+    /// rates used for the computation
+    pub rates: Rates,
+    /// invoice total the contributions are computed on
+    pub base: Money,
+    /// base of CSG and CRDS
+    pub csg_crds_base: Money,
+    pub csg: Money,
+    pub crds: Money,
+    pub vieillesse_plafonnee: Money,
+    pub vieillesse_deplafonnee: Money,
+    /// total withheld by the diffuseur
+    pub total: Money,
+    /// amount actually paid to the author
+    pub net: Money,
+}
+
+impl Withholding {
+    pub fn compute(products: &[Product], rates: Rates) -> Self {
+        let base: Money = products.iter().map(|product| product.total).sum();
+        // each contribution is rounded to the cent, the total is their sum
+        let csg_crds_base = base.percent(rates.csg_crds_base);
+        let csg = csg_crds_base.percent(rates.csg);
+        let crds = csg_crds_base.percent(rates.crds);
+        let vieillesse_plafonnee = base.percent(rates.vieillesse_plafonnee);
+        let vieillesse_deplafonnee = base.percent(rates.vieillesse_deplafonnee);
+        let total = csg + crds + vieillesse_plafonnee + vieillesse_deplafonnee;
+
+        Self {
+            rates,
+            base,
+            csg_crds_base,
+            csg,
+            crds,
+            vieillesse_plafonnee,
+            vieillesse_deplafonnee,
+            total,
+            net: base - total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn products(totals: &[i64]) -> Vec<Product> {
+        totals.iter()
+            .map(|cents| Product { total: Money::from_cents(*cents), ..Product::default() })
+            .collect()
+    }
+
+    #[test]
+    fn rates_of_year() {
+        let rates = [
+            Rates::default(),
+            Rates { year: 2025, vieillesse_plafonnee: 7.0, ..Rates::default() },
+        ];
+        assert_eq!(Rates::for_year(&rates, 2019).vieillesse_plafonnee, 6.9);
+        assert_eq!(Rates::for_year(&rates, 2024).vieillesse_plafonnee, 6.9);
+        assert_eq!(Rates::for_year(&rates, 2025).vieillesse_plafonnee, 7.0);
+        assert_eq!(Rates::for_year(&rates, 2030).vieillesse_plafonnee, 7.0);
+        // before the first entry
+        assert_eq!(Rates::for_year(&rates[1..], 2020), Rates::default());
+    }
+
+    /// example of the sécurité sociale des artistes-auteurs for 1 000 € gross, 2019 rates
+    #[test]
+    fn withholding_example() {
+        let withholding = Withholding::compute(&products(&[60000, 40000]), Rates::default());
+        assert_eq!(withholding.base, Money::from_cents(100000));
+        assert_eq!(withholding.csg_crds_base, Money::from_cents(98250));
+        assert_eq!(withholding.csg, Money::from_cents(9039));
+        assert_eq!(withholding.crds, Money::from_cents(491));
+        assert_eq!(withholding.vieillesse_plafonnee, Money::from_cents(6900));
+        assert_eq!(withholding.vieillesse_deplafonnee, Money::from_cents(400));
+        assert_eq!(withholding.total, Money::from_cents(16830));
+        assert_eq!(withholding.net, Money::from_cents(83170));
+    }
+
+    #[test]
+    fn withholding_net_is_gross_minus_contributions() {
+        for cents in [0, 1, 999, 12345, 100001, 3333333] {
+            let withholding = Withholding::compute(&products(&[cents]), Rates::default());
+            let contributions = withholding.csg + withholding.crds
+                + withholding.vieillesse_plafonnee + withholding.vieillesse_deplafonnee;
+            assert_eq!(withholding.total, contributions);
+            assert_eq!(withholding.net + withholding.total, Money::from_cents(cents));
+        }
+    }
+
+    #[test]
+    fn diffuseur_contributions() {
+        let contributions = DiffuseurContributions::compute(&products(&[100000]), Rates::default());
+        assert_eq!(contributions.social_security, Money::from_cents(1000));
+        assert_eq!(contributions.professional_training, Money::from_cents(100));
+        assert_eq!(contributions.total, Money::from_cents(1100));
+
+        // rounded to the cent for each contribution
+        let contributions = DiffuseurContributions::compute(&products(&[12345]), Rates::default());
+        assert_eq!(contributions.social_security, Money::from_cents(123));
+        assert_eq!(contributions.professional_training, Money::from_cents(12));
+        assert_eq!(contributions.total, Money::from_cents(135));
+    }
+}
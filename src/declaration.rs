@@ -0,0 +1,213 @@
+//! revenue to declare to the URSSAF over a declaration period,
+//! from the payments received for the archived invoices
+
+use chrono::NaiveDate;
+
+use crate::archive::Entry;
+use crate::money::Money;
+
+
+/// how the artist-author contributions of an invoice are paid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// withheld by the diffuseur (précompte)
+    Withheld,
+    /// invoiced to a diffuseur with a dispense de précompte
+    Dispensed,
+    /// invoiced to a client who isn't a diffuseur
+    Direct,
+}
+
+impl Activity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Activity::Withheld => "Diffuseurs, cotisations précomptées",
+            Activity::Dispensed => "Diffuseurs, dispense de précompte",
+            Activity::Direct => "Clients non diffuseurs",
+        }
+    }
+
+    fn of(entry: &Entry) -> Activity {
+        match (entry.invoice.diffuseur, entry.invoice.withholding.is_some()) {
+            (true, true) => Activity::Withheld,
+            (true, false) => Activity::Dispensed,
+            (false, _) => Activity::Direct,
+        }
+    }
+}
+
+/// gross amounts collected during the period
+#[derive(Debug, Clone, Default)]
+pub struct Declaration {
+    /// indexed by activity
+    pub activities: [Money; 3],
+    /// part of the total paid for transfers of rights
+    pub rights: Money,
+    pub total: Money,
+    /// contributions already paid by the diffuseurs on the collected invoices
+    pub diffuseurs: Vec<DiffuseurPayment>,
+}
+
+impl Declaration {
+    pub fn activity(&self, activity: Activity) -> Money {
+        self.activities[activity as usize]
+    }
+}
+
+/// contributions of a diffuseur invoice, in proportion of the amount collected during the period
+#[derive(Debug, Clone)]
+pub struct DiffuseurPayment {
+    pub number: String,
+    pub client: String,
+    /// gross amount collected
+    pub collected: Money,
+    /// social security and professional training contributions of the diffuseur
+    pub contributions: Money,
+    /// artist-author contributions withheld by the diffuseur
+    pub withheld: Money,
+}
+
+/// amounts collected between start and end included
+///
+/// payments are net of the contributions withheld, they are declared gross
+pub fn declare(entries: &[Entry], start: NaiveDate, end: NaiveDate) -> Declaration {
+    let mut declaration = Declaration::default();
+    for entry in entries.iter().filter(|entry| !entry.invoice.is_devis) {
+        let invoice = &entry.invoice;
+        let collected: Money = entry.payments.iter()
+            .filter(|payment| (start..=end).contains(&payment.date))
+            .map(|payment| payment.amount)
+            .sum();
+        let due = invoice.amount_due();
+        if collected == Money::ZERO || due == Money::ZERO {
+            continue;
+        }
+        // share of the invoice collected during the period, rounded to the cent for each invoice
+        let gross = invoice.total().prorata(collected, due);
+
+        declaration.activities[Activity::of(entry) as usize] += gross;
+        declaration.total += gross;
+        if let Some(split) = &invoice.remuneration_split {
+            declaration.rights += split.rights.prorata(collected, due);
+        }
+        if invoice.diffuseur {
+            declaration.diffuseurs.push(DiffuseurPayment {
+                number: invoice.number.clone(),
+                client: invoice.client.name.clone(),
+                collected: gross,
+                contributions: invoice.contributions.map(|c| c.total).unwrap_or_default().prorata(collected, due),
+                withheld: invoice.withholding.map(|w| w.total).unwrap_or_default().prorata(collected, due),
+            });
+        }
+    }
+    declaration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{Client, Product, RemunerationSplit};
+    use crate::archive::{Payment, PaymentMethod};
+    use crate::contributions::{DiffuseurContributions, Rates, Withholding};
+    use crate::latex::InvoiceData;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn entry(number: &str, cents: i64, payments: &[(NaiveDate, i64)]) -> Entry {
+        Entry {
+            invoice: InvoiceData {
+                number: number.to_string(),
+                client: Client { name: "Dupont".to_string(), ..Client::new() },
+                products: vec![Product { total: Money::from_cents(cents), ..Product::default() }],
+                date: date(2025, 1, 15),
+                ..InvoiceData::default()
+            },
+            payments: payments.iter()
+                .map(|&(date, cents)| Payment { date, amount: Money::from_cents(cents), method: PaymentMethod::Transfer })
+                .collect(),
+        }
+    }
+
+    fn first_quarter(entries: &[Entry]) -> Declaration {
+        declare(entries, date(2025, 1, 1), date(2025, 3, 31))
+    }
+
+    #[test]
+    fn payments_of_the_period() {
+        let entries = [entry("F-001", 100000, &[
+            (date(2024, 12, 31), 10000),
+            (date(2025, 1, 1), 20000),
+            (date(2025, 3, 31), 30000),
+            (date(2025, 4, 1), 40000),
+        ])];
+        let declaration = first_quarter(&entries);
+        assert_eq!(declaration.total, Money::from_cents(50000));
+        assert_eq!(declaration.activity(Activity::Direct), Money::from_cents(50000));
+        assert!(declaration.diffuseurs.is_empty());
+
+        let declaration = declare(&entries, date(2025, 4, 1), date(2025, 6, 30));
+        assert_eq!(declaration.total, Money::from_cents(40000));
+    }
+
+    #[test]
+    fn quotes_and_unpaid_invoices() {
+        let mut quote = entry("D-001", 100000, &[(date(2025, 2, 1), 100000)]);
+        quote.invoice.is_devis = true;
+        let declaration = first_quarter(&[quote, entry("F-001", 100000, &[])]);
+        assert_eq!(declaration.total, Money::ZERO);
+    }
+
+    #[test]
+    fn withheld_contributions_are_declared_gross() {
+        let mut entry = entry("F-001", 100000, &[(date(2025, 2, 1), 41585)]);
+        entry.invoice.diffuseur = true;
+        entry.invoice.withholding = Some(Withholding::compute(&entry.invoice.products, Rates::default()));
+        entry.invoice.contributions = Some(DiffuseurContributions::compute(&entry.invoice.products, Rates::default()));
+        let declaration = first_quarter(&[entry]);
+
+        // half of the 831,70 € net
+        assert_eq!(declaration.activity(Activity::Withheld), Money::from_cents(50000));
+        assert_eq!(declaration.total, Money::from_cents(50000));
+        let [diffuseur] = &declaration.diffuseurs[..] else { panic!("{:?}", declaration.diffuseurs) };
+        assert_eq!(diffuseur.number, "F-001");
+        assert_eq!(diffuseur.client, "Dupont");
+        assert_eq!(diffuseur.collected, Money::from_cents(50000));
+        assert_eq!(diffuseur.withheld, Money::from_cents(8415));
+        assert_eq!(diffuseur.contributions, Money::from_cents(550));
+    }
+
+    #[test]
+    fn dispensed_diffuseur() {
+        let mut entry = entry("F-001", 100000, &[(date(2025, 2, 1), 100000)]);
+        entry.invoice.diffuseur = true;
+        let declaration = first_quarter(&[entry]);
+        assert_eq!(declaration.activity(Activity::Dispensed), Money::from_cents(100000));
+        assert_eq!(declaration.activity(Activity::Withheld), Money::ZERO);
+        assert_eq!(declaration.diffuseurs[0].withheld, Money::ZERO);
+    }
+
+    #[test]
+    fn prorata_of_rights_and_vat() {
+        let mut entry = entry("F-001", 100000, &[(date(2025, 2, 1), 40000)]);
+        entry.invoice.vat = Money::from_cents(20000);
+        entry.invoice.remuneration_split = Some(RemunerationSplit {
+            work_share: 60.0,
+            work: Money::from_cents(60000),
+            rights_share: 40.0,
+            rights: Money::from_cents(40000),
+        });
+        let declaration = first_quarter(&[entry]);
+
+        // a third of the 1 200 € due, VAT excluded
+        assert_eq!(declaration.total, Money::from_cents(33333));
+        assert_eq!(declaration.rights, Money::from_cents(13333));
+    }
+
+    #[test]
+    fn refunded_credit_note() {
+        let declaration = first_quarter(&[entry("A-001", -30000, &[(date(2025, 2, 1), -30000)])]);
+        assert_eq!(declaration.total, Money::from_cents(-30000));
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+
+/// warn about next year's dispense this many days before the end of the year
+const EXPIRY_WARNING_DAYS: i64 = 30;
+
+/// dispense de précompte, issued by the Urssaf for a calendar year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dispense {
+    pub year: i32,
+    pub path: PathBuf,
+}
+
+/// dispenses of all years
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Dispenses {
+    dispenses: Vec<Dispense>,
+}
+
+impl Dispenses {
+    /// dispense file valid for `year`, if any
+    pub fn for_year(&self, year: i32) -> Option<&Path> {
+        self.dispenses.iter()
+            .find(|dispense| dispense.year == year)
+            .map(|dispense| dispense.path.as_path())
+    }
+
+    /// dispense file valid at `date`, if it still exists
+    pub fn for_date(&self, date: NaiveDate) -> Option<&Path> {
+        self.for_year(date.year()).filter(|path| path.exists())
+    }
+
+    /// add or replace the dispense of `year`
+    pub fn set(&mut self, year: i32, path: PathBuf) {
+        self.dispenses.retain(|dispense| dispense.year != year);
+        self.dispenses.push(Dispense { year, path });
+        self.dispenses.sort_by_key(|dispense| dispense.year);
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Dispense> {
+        self.dispenses.iter_mut()
+    }
+
+    /// warning to display about the dispense needed at `date`, knowing we are `today`
+    pub fn warning(&self, date: NaiveDate, today: NaiveDate) -> Option<String> {
+        match self.for_year(date.year()) {
+            None => return Some(format!("Aucune dispense pour {}", date.year())),
+            Some(path) if !path.exists() => {
+                return Some(format!("Fichier introuvable : {}", path.display()))
+            }
+            Some(_) => (),
+        }
+
+        // dispenses expire at the end of the civil year
+        let next_year = today.year() + 1;
+        let days_left = NaiveDate::from_ymd_opt(next_year, 1, 1)
+            .map(|new_year| new_year.signed_duration_since(today).num_days())
+            .unwrap_or(i64::MAX);
+        if date.year() == today.year()
+            && days_left <= EXPIRY_WARNING_DAYS
+            && self.for_year(next_year).is_none() {
+            return Some(format!("La dispense {} expire dans {} jours", today.year(), days_left));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// a file that exists wherever the tests run
+    fn existing() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")
+    }
+
+    fn with_years(years: &[i32]) -> Dispenses {
+        let mut dispenses = Dispenses::default();
+        for year in years {
+            dispenses.set(*year, existing());
+        }
+        dispenses
+    }
+
+    #[test]
+    fn missing_year() {
+        let dispenses = with_years(&[2024]);
+        assert_eq!(dispenses.warning(date(2025, 3, 1), date(2025, 3, 1)), Some("Aucune dispense pour 2025".to_string()));
+        assert_eq!(dispenses.warning(date(2024, 3, 1), date(2024, 3, 1)), None);
+    }
+
+    #[test]
+    fn missing_file() {
+        let mut dispenses = Dispenses::default();
+        dispenses.set(2025, PathBuf::from("/nonexistent/dispense.pdf"));
+        assert_eq!(
+            dispenses.warning(date(2025, 3, 1), date(2025, 3, 1)),
+            Some("Fichier introuvable : /nonexistent/dispense.pdf".to_string()),
+        );
+        assert_eq!(dispenses.for_date(date(2025, 3, 1)), None);
+    }
+
+    #[test]
+    fn expiring() {
+        let dispenses = with_years(&[2025]);
+        assert_eq!(dispenses.warning(date(2025, 12, 1), date(2025, 12, 1)), None);
+        assert_eq!(
+            dispenses.warning(date(2025, 12, 2), date(2025, 12, 2)),
+            Some("La dispense 2025 expire dans 30 jours".to_string()),
+        );
+        assert_eq!(
+            dispenses.warning(date(2025, 12, 31), date(2025, 12, 31)),
+            Some("La dispense 2025 expire dans 1 jours".to_string()),
+        );
+        // next year's dispense is already there
+        assert_eq!(with_years(&[2025, 2026]).warning(date(2025, 12, 2), date(2025, 12, 2)), None);
+    }
+
+    #[test]
+    fn turn_of_the_year() {
+        // invoice dated next year, issued before the new year
+        assert_eq!(
+            with_years(&[2025]).warning(date(2026, 1, 5), date(2025, 12, 28)),
+            Some("Aucune dispense pour 2026".to_string()),
+        );
+        assert_eq!(with_years(&[2025, 2026]).warning(date(2026, 1, 5), date(2025, 12, 28)), None);
+        // invoice of last year, issued after the new year
+        assert_eq!(with_years(&[2025]).warning(date(2025, 12, 20), date(2026, 1, 2)), None);
+        assert_eq!(with_years(&[2026]).warning(date(2026, 1, 1), date(2026, 1, 1)), None);
+    }
+
+    #[test]
+    fn set_replaces_the_year() {
+        let mut dispenses = with_years(&[2026, 2024]);
+        dispenses.set(2024, PathBuf::from("/other.pdf"));
+        assert_eq!(dispenses.for_year(2024), Some(Path::new("/other.pdf")));
+        let years: Vec<i32> = dispenses.iter_mut().map(|dispense| dispense.year).collect();
+        assert_eq!(years, [2024, 2026]);
+    }
+}
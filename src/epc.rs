@@ -0,0 +1,60 @@
+//! EPC QR code (EPC069-12) of a SEPA credit transfer, scanned by banking apps
+//! to fill the beneficiary, amount and reference of the payment
+
+use std::path::Path;
+
+use image::Luma;
+use qrcode::{EcLevel, QrCode};
+
+use crate::latex::InvoiceData;
+use crate::money::Money;
+use crate::validation;
+
+/// lengths in characters and amount allowed by the standard
+const MAX_NAME: usize = 70;
+const MAX_REMITTANCE: usize = 140;
+const MAX_AMOUNT: Money = Money::from_cents(99_999_999_999);
+
+/// content of the QR code, none when the bank details or the amount can't be encoded
+pub fn payload(invoice: &InvoiceData) -> Option<String> {
+    let author = &invoice.author;
+    let iban = author.iban.as_deref().filter(|iban| validation::iban(iban).is_ok())?;
+    // the BIC is optional within the EEA since version 002
+    let bic = author.bic.as_deref()
+        .filter(|bic| validation::bic(bic).is_ok())
+        .map(validation::normalize)
+        .unwrap_or_default();
+    let amount = invoice.amount_due();
+    if !(Money::from_cents(1)..=MAX_AMOUNT).contains(&amount) {
+        return None;
+    }
+
+    Some([
+        "BCD",
+        "002",
+        // UTF-8
+        "1",
+        "SCT",
+        &bic,
+        &truncate(&author.title.replace('\n', " "), MAX_NAME),
+        &validation::normalize(iban),
+        &format!("EUR{amount}"),
+        // purpose and structured reference, unused
+        "",
+        "",
+        &truncate(&format!("Facture {}", invoice.number), MAX_REMITTANCE),
+    ].join("\n"))
+}
+
+pub fn write_png(payload: &str, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let code = QrCode::with_error_correction_level(payload, EcLevel::M)?;
+    code.render::<Luma<u8>>()
+        .min_dimensions(400, 400)
+        .build()
+        .save(path)?;
+    Ok(())
+}
+
+fn truncate(value: &str, max: usize) -> String {
+    value.trim().chars().take(max).collect()
+}
@@ -0,0 +1,427 @@
+//! Fichier des Écritures Comptables (article A47 A-1 du livre des procédures fiscales),
+//! generated from the archived invoices and the payments received for them
+
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::app::Client;
+use crate::archive::{Entry, Payment, PaymentMethod};
+use crate::money::Money;
+use crate::validation;
+
+
+/// account numbers of the plan comptable général used by the entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Accounts {
+    /// collective account, each client gets an auxiliary account
+    pub clients: String,
+    pub revenue: String,
+    pub vat: String,
+    pub bank: String,
+    pub cash: String,
+    /// contributions withheld by the diffuseur (précompte)
+    pub withholding: String,
+}
+
+impl Default for Accounts {
+    fn default() -> Self {
+        Self {
+            clients: "411000".to_string(),
+            revenue: "706000".to_string(),
+            vat: "445710".to_string(),
+            bank: "512000".to_string(),
+            cash: "530000".to_string(),
+            withholding: "646000".to_string(),
+        }
+    }
+}
+
+/// accounts as edited in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Account {
+    Clients,
+    Revenue,
+    Vat,
+    Bank,
+    Cash,
+    Withholding,
+}
+
+impl Account {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Account::Clients => "Clients",
+            Account::Revenue => "Prestations de services",
+            Account::Vat => "TVA collectée",
+            Account::Bank => "Banque",
+            Account::Cash => "Caisse",
+            Account::Withholding => "Cotisations précomptées",
+        }
+    }
+
+    pub fn get(&self, accounts: &Accounts) -> String {
+        match self {
+            Account::Clients => accounts.clients.clone(),
+            Account::Revenue => accounts.revenue.clone(),
+            Account::Vat => accounts.vat.clone(),
+            Account::Bank => accounts.bank.clone(),
+            Account::Cash => accounts.cash.clone(),
+            Account::Withholding => accounts.withholding.clone(),
+        }
+    }
+
+    pub fn set(&self, accounts: &mut Accounts, number: String) {
+        match self {
+            Account::Clients => accounts.clients = number,
+            Account::Revenue => accounts.revenue = number,
+            Account::Vat => accounts.vat = number,
+            Account::Bank => accounts.bank = number,
+            Account::Cash => accounts.cash = number,
+            Account::Withholding => accounts.withholding = number,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Journal {
+    Sales,
+    Bank,
+    Cash,
+}
+
+impl Journal {
+    fn code(&self) -> &'static str {
+        match self {
+            Journal::Sales => "VE",
+            Journal::Bank => "BQ",
+            Journal::Cash => "CA",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Journal::Sales => "Ventes",
+            Journal::Bank => "Banque",
+            Journal::Cash => "Caisse",
+        }
+    }
+}
+
+/// balanced accounting entry of an invoice or a payment
+#[derive(Debug, Clone)]
+pub struct Ecriture {
+    journal: Journal,
+    date: NaiveDate,
+    /// invoice number
+    piece: String,
+    piece_date: NaiveDate,
+    label: String,
+    lines: Vec<Line>,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    account: String,
+    account_label: &'static str,
+    /// auxiliary account number and label, for the client lines
+    auxiliary: Option<(String, String)>,
+    /// debit when positive, credit when negative
+    amount: Money,
+}
+
+/// columns defined by the administration, in order
+const COLUMNS: [&str; 18] = [
+    "JournalCode", "JournalLib", "EcritureNum", "EcritureDate", "CompteNum", "CompteLib",
+    "CompAuxNum", "CompAuxLib", "PieceRef", "PieceDate", "EcritureLib", "Debit", "Credit",
+    "EcritureLet", "DateLet", "ValidDate", "Montantdevise", "Idevise",
+];
+
+/// entries of the invoices issued and payments received between start and end included,
+/// in chronological order
+///
+/// invoices with a negative total are credit notes (avoirs), their entries are reversed
+pub fn ecritures(entries: &[Entry], accounts: &Accounts, start: NaiveDate, end: NaiveDate) -> Vec<Ecriture> {
+    let mut ecritures: Vec<Ecriture> = entries.iter()
+        .filter(|entry| !entry.invoice.is_devis)
+        .flat_map(|entry| std::iter::once(sale(entry, accounts))
+            .chain(entry.payments.iter().map(|payment| receipt(entry, payment, accounts))))
+        .filter(|ecriture| (start..=end).contains(&ecriture.date))
+        .collect();
+    ecritures.sort_by(|a, b| a.date.cmp(&b.date)
+        .then_with(|| a.journal.cmp(&b.journal))
+        .then_with(|| a.piece.cmp(&b.piece)));
+    ecritures
+}
+
+/// `<SIREN>FEC<closing date>.txt`, as expected by the administration
+pub fn file_name(siret: &str, end: NaiveDate) -> String {
+    let siren: String = validation::normalize(siret).chars().take(9).collect();
+    format!("{siren}FEC{}.txt", end.format("%Y%m%d"))
+}
+
+/// tab separated, entries numbered in chronological order
+pub fn write_file(path: &Path, ecritures: &[Ecriture]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(::csv::QuoteStyle::Never)
+        .from_path(path)?;
+    writer.write_record(COLUMNS)?;
+    for (number, ecriture) in ecritures.iter().enumerate() {
+        for line in &ecriture.lines {
+            let (auxiliary, auxiliary_label) = line.auxiliary.clone().unwrap_or_default();
+            writer.write_record([
+                ecriture.journal.code().to_string(),
+                ecriture.journal.label().to_string(),
+                (number + 1).to_string(),
+                fec_date(ecriture.date),
+                line.account.clone(),
+                line.account_label.to_string(),
+                auxiliary,
+                sanitize(&auxiliary_label),
+                sanitize(&ecriture.piece),
+                fec_date(ecriture.piece_date),
+                sanitize(&ecriture.label),
+                // decimal comma, no thousands separator
+                line.amount.max(Money::ZERO).french(),
+                (-line.amount).max(Money::ZERO).french(),
+                String::new(),
+                String::new(),
+                fec_date(ecriture.date),
+                String::new(),
+                String::new(),
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// revenue of the invoice, and the contributions withheld on it by the diffuseur
+fn sale(entry: &Entry, accounts: &Accounts) -> Ecriture {
+    let invoice = &entry.invoice;
+    let gross = invoice.total();
+    let kind = if gross < Money::ZERO { "Avoir" } else { "Facture" };
+    let vat = invoice.vat;
+
+    let mut lines = vec![
+        client_line(entry, accounts, gross + vat),
+        Line { account: accounts.revenue.clone(), account_label: Account::Revenue.label(), auxiliary: None, amount: -gross },
+    ];
+    if vat != Money::ZERO {
+        lines.push(Line { account: accounts.vat.clone(), account_label: Account::Vat.label(), auxiliary: None, amount: -vat });
+    }
+    if let Some(withholding) = &invoice.withholding {
+        lines.push(Line {
+            account: accounts.withholding.clone(),
+            account_label: Account::Withholding.label(),
+            auxiliary: None,
+            amount: withholding.total,
+        });
+        lines.push(client_line(entry, accounts, -withholding.total));
+    }
+
+    Ecriture {
+        journal: Journal::Sales,
+        date: invoice.date,
+        piece: invoice.number.clone(),
+        piece_date: invoice.date,
+        label: format!("{kind} {} {}", invoice.number, invoice.client.name),
+        lines,
+    }
+}
+
+fn receipt(entry: &Entry, payment: &Payment, accounts: &Accounts) -> Ecriture {
+    let (journal, account, account_label) = match payment.method {
+        PaymentMethod::Cash => (Journal::Cash, accounts.cash.clone(), Account::Cash.label()),
+        _ => (Journal::Bank, accounts.bank.clone(), Account::Bank.label()),
+    };
+    Ecriture {
+        journal,
+        date: payment.date,
+        piece: entry.invoice.number.clone(),
+        piece_date: entry.invoice.date,
+        label: format!("Règlement {} {} ({})", entry.invoice.number, entry.invoice.client.name, payment.method.label()),
+        lines: vec![
+            Line { account, account_label, auxiliary: None, amount: payment.amount },
+            client_line(entry, accounts, -payment.amount),
+        ],
+    }
+}
+
+fn client_line(entry: &Entry, accounts: &Accounts, amount: Money) -> Line {
+    let client = &entry.invoice.client;
+    Line {
+        account: accounts.clients.clone(),
+        account_label: Account::Clients.label(),
+        auxiliary: Some((auxiliary_account(client), client.name.clone())),
+        amount,
+    }
+}
+
+/// auxiliary account of a client, from its id so that clients with similar names don't share it:
+/// C + the first 8 characters of the id
+///
+/// invoices archived before clients had an id and never linked to one fall back to the name
+fn auxiliary_account(client: &Client) -> String {
+    if client.id.is_nil() {
+        let code: String = crate::fuzzy::normalize(&client.name)
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .take(10)
+            .collect();
+        return format!("C{}", code.to_uppercase());
+    }
+    let id = client.id.simple().to_string();
+    format!("C{}", id[..8].to_uppercase())
+}
+
+fn fec_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// tabs and line breaks would break the columns
+fn sanitize(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Product;
+    use crate::contributions::{Rates, Withholding};
+    use crate::latex::InvoiceData;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn products(totals: &[i64]) -> Vec<Product> {
+        totals.iter()
+            .map(|cents| Product { total: Money::from_cents(*cents), ..Product::default() })
+            .collect()
+    }
+
+    fn entry(number: &str, date: NaiveDate, totals: &[i64]) -> Entry {
+        let mut client = Client::new();
+        client.name = "Dupont".to_string();
+        Entry {
+            invoice: InvoiceData {
+                number: number.to_string(),
+                client,
+                products: products(totals),
+                date,
+                ..InvoiceData::default()
+            },
+            payments: Vec::new(),
+        }
+    }
+
+    fn payment(date: NaiveDate, cents: i64, method: PaymentMethod) -> Payment {
+        Payment { date, amount: Money::from_cents(cents), method }
+    }
+
+    fn balance(ecriture: &Ecriture) -> Money {
+        ecriture.lines.iter().map(|line| line.amount).sum()
+    }
+
+    fn amount(ecriture: &Ecriture, account: &str) -> Money {
+        ecriture.lines.iter().filter(|line| line.account == account).map(|line| line.amount).sum()
+    }
+
+    fn year(entries: &[Entry], year: i32) -> Vec<Ecriture> {
+        ecritures(entries, &Accounts::default(), date(year, 1, 1), date(year, 12, 31))
+    }
+
+    #[test]
+    fn sale_and_receipt() {
+        let mut entry = entry("F-001", date(2025, 3, 1), &[60000, 40000]);
+        entry.payments.push(payment(date(2025, 3, 20), 30000, PaymentMethod::Cash));
+        entry.payments.push(payment(date(2025, 3, 10), 70000, PaymentMethod::Transfer));
+        let ecritures = year(&[entry], 2025);
+
+        assert_eq!(ecritures.len(), 3);
+        assert!(ecritures.iter().all(|ecriture| balance(ecriture) == Money::ZERO));
+        let [sale, transfer, cash] = &ecritures[..] else { unreachable!() };
+
+        assert_eq!(sale.journal, Journal::Sales);
+        assert_eq!(sale.label, "Facture F-001 Dupont");
+        assert_eq!(amount(sale, "411000"), Money::from_cents(100000));
+        assert_eq!(amount(sale, "706000"), Money::from_cents(-100000));
+        assert_eq!(sale.lines.len(), 2);
+
+        assert_eq!(transfer.journal, Journal::Bank);
+        assert_eq!(transfer.date, date(2025, 3, 10));
+        assert_eq!(transfer.piece_date, date(2025, 3, 1));
+        assert_eq!(amount(transfer, "512000"), Money::from_cents(70000));
+        assert_eq!(cash.journal, Journal::Cash);
+        assert_eq!(amount(cash, "530000"), Money::from_cents(30000));
+        assert_eq!(amount(cash, "411000"), Money::from_cents(-30000));
+    }
+
+    #[test]
+    fn vat() {
+        let mut entry = entry("F-001", date(2025, 3, 1), &[100000]);
+        entry.invoice.vat = Money::from_cents(20000);
+        let ecritures = year(&[entry], 2025);
+
+        assert_eq!(balance(&ecritures[0]), Money::ZERO);
+        assert_eq!(amount(&ecritures[0], "411000"), Money::from_cents(120000));
+        assert_eq!(amount(&ecritures[0], "706000"), Money::from_cents(-100000));
+        assert_eq!(amount(&ecritures[0], "445710"), Money::from_cents(-20000));
+    }
+
+    #[test]
+    fn credit_note() {
+        let entry = entry("A-001", date(2025, 3, 1), &[-30000]);
+        let ecritures = year(&[entry], 2025);
+
+        assert_eq!(balance(&ecritures[0]), Money::ZERO);
+        assert_eq!(ecritures[0].label, "Avoir A-001 Dupont");
+        // the client is credited and the revenue debited
+        assert_eq!(amount(&ecritures[0], "411000"), Money::from_cents(-30000));
+        assert_eq!(amount(&ecritures[0], "706000"), Money::from_cents(30000));
+    }
+
+    #[test]
+    fn withholding() {
+        let mut entry = entry("F-001", date(2025, 3, 1), &[100000]);
+        entry.invoice.withholding = Some(Withholding::compute(&entry.invoice.products, Rates::default()));
+        let ecritures = year(&[entry], 2025);
+
+        assert_eq!(balance(&ecritures[0]), Money::ZERO);
+        assert_eq!(ecritures[0].lines.len(), 4);
+        assert_eq!(amount(&ecritures[0], "646000"), Money::from_cents(16830));
+        // the client owes the net amount
+        assert_eq!(amount(&ecritures[0], "411000"), Money::from_cents(83170));
+        assert_eq!(amount(&ecritures[0], "706000"), Money::from_cents(-100000));
+    }
+
+    #[test]
+    fn period() {
+        let mut december = entry("F-001", date(2024, 12, 31), &[100000]);
+        december.payments.push(payment(date(2025, 1, 2), 100000, PaymentMethod::Transfer));
+        let january = entry("F-002", date(2025, 1, 1), &[50000]);
+        let mut devis = entry("D-001", date(2025, 1, 1), &[50000]);
+        devis.invoice.is_devis = true;
+        let entries = [december, january, devis];
+
+        let previous = year(&entries, 2024);
+        assert_eq!(previous.len(), 1);
+        assert_eq!(previous[0].piece, "F-001");
+        assert_eq!(previous[0].journal, Journal::Sales);
+
+        // the payment of the december invoice is booked in january, after the january invoice
+        let current = year(&entries, 2025);
+        assert_eq!(current.len(), 2);
+        assert_eq!((current[0].piece.as_str(), current[0].journal), ("F-002", Journal::Sales));
+        assert_eq!((current[1].piece.as_str(), current[1].journal), ("F-001", Journal::Bank));
+    }
+
+    #[test]
+    fn siren_in_file_name() {
+        assert_eq!(file_name("404 833 048 00022", date(2024, 12, 31)), "404833048FEC20241231.txt");
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::APP_NAME;
+
+
+/// directory of the files imported by the app (signature, dispenses...)
+pub fn files_dir() -> PathBuf {
+    Config::data_dir(APP_NAME).join("files")
+}
+
+/// copy a file into the app data directory, named after the hash of its content,
+/// so it stays available even if the original is moved or deleted
+///
+/// files that were already imported are returned as is
+pub fn import(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = files_dir();
+    if path.parent() == Some(dir.as_path()) {
+        return Ok(path.to_path_buf());
+    }
+
+    let content = fs::read(path)?;
+    let mut file_name = format!("{:x}", Sha256::digest(&content));
+    // keep the extension, latex relies on it to include images and pdfs
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        file_name = format!("{file_name}.{}", extension.to_lowercase());
+    }
+
+    fs::create_dir_all(&dir)?;
+    let imported = dir.join(file_name);
+    if !imported.exists() {
+        fs::write(&imported, content)?;
+    }
+    Ok(imported)
+}
+
+/// import the file if it exists, otherwise keep the path unchanged
+pub fn import_or_keep(path: PathBuf) -> PathBuf {
+    match import(&path) {
+        Ok(imported) => imported,
+        Err(err) => {
+            println!("Couldn't import {}: {}", path.display(), err);
+            path
+        }
+    }
+}
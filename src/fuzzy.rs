@@ -0,0 +1,54 @@
+/// lowercase text without accents, so that "Société" matches "societe"
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+/// score of a normalized `pattern` found in a normalized `text`, higher is better
+///
+/// substrings score above scattered matches, and matches at the start of a word
+/// above matches in the middle of one. `None` when the pattern characters can't
+/// be found in order.
+pub fn score(pattern: &str, text: &str) -> Option<u32> {
+    if pattern.is_empty() { return Some(0); }
+
+    if let Some(position) = text.find(pattern) {
+        let at_word_start = position == 0
+            || !text[..position].chars().last().is_some_and(char::is_alphanumeric);
+        let bonus = if position == 0 { 200 } else if at_word_start { 100 } else { 0 };
+        return Some(1000 + bonus - (position as u32).min(99));
+    }
+
+    // every pattern character must appear in order
+    let mut score = 0;
+    let mut previous_matched = false;
+    let mut previous_char = None;
+    let mut pattern_chars = pattern.chars().peekable();
+    for c in text.chars() {
+        let Some(&expected) = pattern_chars.peek() else { break };
+        if c == expected {
+            pattern_chars.next();
+            score += 1;
+            if previous_matched { score += 4; }
+            if !previous_char.is_some_and(char::is_alphanumeric) { score += 6; }
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+        previous_char = Some(c);
+    }
+    if pattern_chars.peek().is_some() { return None; }
+    Some(score)
+}
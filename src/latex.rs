@@ -3,10 +3,15 @@ use std::io::Write;
 use std::path::Path;
 use std::process::Command;
 
+use chrono::NaiveDate;
 use handlebars::{to_json, Context, Helper, Output, RenderContext, RenderError, RenderErrorReason, Handlebars};
 use serde_json::Map;
 
-use crate::app::{Author, Client, Product};
+use crate::app::{Attachment, Author, Client, LegalTerms, PaymentTerms, Product, RemunerationSplit, RightsTransfer, ServiceDate, Unit};
+use crate::contributions::{DiffuseurContributions, Withholding};
+use crate::epc;
+use crate::money::Money;
+use crate::validation;
 
 
 // /// Example usage including all functionalities:
@@ -38,7 +43,7 @@ const TEMPLATE_STR: &str = include_str!("../assets/template.tex");
 ///     products: vec![product.clone(), product2],
 ///     diffuseur: false,
 /// };
-#[derive(Clone)]
+#[derive(Clone, Default)]
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct InvoiceData {
     pub author: Author,
@@ -47,10 +52,53 @@ pub struct InvoiceData {
     pub client: Client,
     pub nature: String,
     pub diffuseur: bool,
+    /// contributions due by the diffuseur, only when `diffuseur` is set
+    pub contributions: Option<DiffuseurContributions>,
+    /// contributions withheld by the diffuseur when the author has no dispense
+    pub withholding: Option<Withholding>,
     pub dispense_path: String,
     pub products: Vec<Product>,
-    /// date with format dd/mm/yyyy
-    pub date: String,
+    /// VAT collected on top of the products total, zero under the franchise en base
+    #[serde(default)]
+    pub vat: Money,
+    pub date: NaiveDate,
+    pub payment_terms: PaymentTerms,
+    pub due_date: NaiveDate,
+    /// devis validity in days
+    pub devis_validity: u32,
+    /// number of the devis accepted by this invoice
+    #[serde(default)]
+    pub quote: Option<String>,
+    /// date de prestation, when it differs from the invoice date
+    pub service_date: Option<ServiceDate>,
+    /// late payment penalties and legal mentions of the author profile
+    pub legal: LegalTerms,
+    /// appended as annex pages, in order
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// rendered as an annex when set
+    pub rights_transfer: Option<RightsTransfer>,
+    /// computed from the products total, set with `rights_transfer`
+    pub remuneration_split: Option<RemunerationSplit>,
+    /// EPC QR code image, generated when filling the template
+    #[serde(default)]
+    pub payment_qr_path: Option<String>,
+}
+
+impl InvoiceData {
+    /// sum of the product lines, before the contributions withheld by the diffuseur
+    pub fn total(&self) -> Money {
+        self.products.iter().map(|product| product.total).sum()
+    }
+
+    /// amount paid by the client, net of the contributions withheld by the diffuseur
+    pub fn amount_due(&self) -> Money {
+        let net = match &self.withholding {
+            Some(withholding) => withholding.net,
+            None => self.total(),
+        };
+        net + self.vat
+    }
 }
 
 
@@ -60,7 +108,6 @@ pub struct Template {
     filled: bool
 }
 impl Template {
-    #[allow(dead_code)]
     pub fn from(path: &str) -> Result<Template, Box<dyn std::error::Error>> {
         Ok(Template {
             content: read_to_string(Path::new(path))?,
@@ -72,17 +119,14 @@ impl Template {
         Template { content: TEMPLATE_STR.to_string(), filled: false }
     }
 
-    pub fn fill(&self, invoice_data: InvoiceData) -> Result<Template, Box<dyn std::error::Error>> {
+    pub fn fill(&self, mut invoice_data: InvoiceData) -> Result<Template, Box<dyn std::error::Error>> {
+        invoice_data.payment_qr_path = payment_qr(&invoice_data)?;
         let mut data = Map::new();
+        // printed as is, the template doesn't compute amounts
+        data.insert("total".to_string(), to_json(invoice_data.total()));
         data.insert("invoice".to_string(), to_json(invoice_data));
-        let mut reg = Handlebars::new();
-        reg.register_helper("frfloat", Box::new(french_float));
-        reg.register_helper("multiline", Box::new(multiline));
-        reg.register_helper("includepdf", Box::new(includepdf));
-        reg.register_helper("override_braces", Box::new(override_braces));
-        reg.set_dev_mode(true);  // This enables alternative delimiters
         Ok(Template {
-            content: reg.render_template(&self.content, &data)?,
+            content: registry().render_template(&self.content, &data)?,
             filled: true,
         })
     }
@@ -98,26 +142,57 @@ impl Template {
 
     pub fn export(self, output_file: &str) -> Result<PdfFile, Box<dyn std::error::Error>> {
         if !self.filled { return Err("Template was not filled".into()) };
-        let tmp_pdf = latex_to_pdf(&self.content)?;
+        let tmp_pdf = latex_to_pdf(&self.content, "document")?;
         std::fs::copy(tmp_pdf.path, output_file)?;
         Ok(PdfFile{ path: output_file.to_string() })
     }
 
     pub fn compile(self) -> Result<PdfFile, Box<dyn std::error::Error>> {
         if !self.filled { return Err("Template was not filled".into()) };
-        latex_to_pdf(&self.content)
+        latex_to_pdf(&self.content, "document")
     }
 }
 
+/// handlebars with the helpers used by the latex templates
+pub fn registry() -> Handlebars<'static> {
+    let mut reg = Handlebars::new();
+    reg.register_helper("frfloat", Box::new(french_float));
+    reg.register_helper("money", Box::new(money));
+    reg.register_helper("frdate", Box::new(french_date));
+    reg.register_helper("multiline", Box::new(multiline));
+    reg.register_helper("includepdf", Box::new(includepdf));
+    reg.register_helper("override_braces", Box::new(override_braces));
+    reg.register_helper("iban", Box::new(iban));
+    reg.register_helper("bic", Box::new(bic));
+    reg.register_helper("quantity", Box::new(quantity));
+    reg.set_dev_mode(true);  // This enables alternative delimiters
+    reg
+}
+
+
+/// EPC QR code written in the compile directory, for invoices of authors who enabled it
+fn payment_qr(invoice: &InvoiceData) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if invoice.is_devis || !invoice.author.payment_qr {
+        return Ok(None);
+    }
+    let Some(payload) = epc::payload(invoice) else {
+        return Ok(None);
+    };
+    std::fs::create_dir_all(TMP_DIR)?;
+    let path = Path::new(TMP_DIR).join("epc-qr.png");
+    epc::write_png(&payload, &path)?;
+    Ok(Some(path.to_str().unwrap().to_string()))
+}
 
-fn latex_to_pdf(latex_content: &str) -> Result<PdfFile, Box<dyn std::error::Error>> {
+/// compile in the tmp directory, to `<name>.pdf`
+pub fn latex_to_pdf(latex_content: &str, name: &str) -> Result<PdfFile, Box<dyn std::error::Error>> {
     let output_dir = Path::new(TMP_DIR);
 
     // Create output directory if it doesn't exist
     std::fs::create_dir_all(output_dir)?;
     
     // Path for the temporary tex file
-    let tex_path = output_dir.join("document.tex");
+    let tex_path = output_dir.join(format!("{name}.tex"));
     
     // Write LaTeX content to file
     let mut file = File::create(&tex_path)?;
@@ -148,7 +223,7 @@ fn latex_to_pdf(latex_content: &str) -> Result<PdfFile, Box<dyn std::error::Erro
     }
     
     // Check if PDF was actually created
-    let pdf_path = output_dir.join("document.pdf");
+    let pdf_path = output_dir.join(format!("{name}.pdf"));
     if !pdf_path.exists() {
         return Err("PDF file wasn't created".into());
     } else {
@@ -249,7 +324,7 @@ fn multiline(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out
 // otherwise latex template will get errors
 // /!\ only works if input param is a valid number with 1 or 0 "."
 fn french_float (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
-    // Get the first parameter (the price)
+    // Get the first parameter (a rate)
     let param = h.param(0).ok_or(
         RenderErrorReason::ParamNotFoundForIndex(
             "french_float helper requires at least one parameter",
@@ -257,16 +332,120 @@ fn french_float (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext,
     ))?;
 
     // Convert to f64
-    let price = param.value().as_f64().ok_or(
+    let rate = param.value().as_f64().ok_or(
         RenderErrorReason::InvalidParamType(
             "french_float helper requires a number parameter",
     ))?;
 
     // Format with comma as decimal separator
-    let formatted = format!("{:.2}", price).replace(".", ",");
+    let formatted = format!("{:.2}", rate).replace(".", ",");
 
     // Write the formatted string to output
     out.write(&formatted)?;
 
     Ok(())
 }
+
+// amounts are exact to the cent, written with a ,
+//     -> 21,40
+fn money (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
+    let param = h.param(0).ok_or(
+        RenderErrorReason::ParamNotFoundForIndex(
+            "money helper requires at least one parameter",
+            0,
+    ))?;
+
+    let amount = serde_json::from_value::<Money>(param.value().clone()).map_err(|_|
+        RenderErrorReason::InvalidParamType(
+            "money helper requires an amount parameter",
+    ))?;
+
+    out.write(&amount.french())?;
+
+    Ok(())
+}
+
+// IBANs are printed by blocks of four characters
+//     -> FR76 3000 6000 0112 3456 7890 189
+fn iban (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
+    let param = h.param(0).ok_or(
+        RenderErrorReason::ParamNotFoundForIndex(
+            "iban helper requires at least one parameter",
+            0,
+    ))?;
+
+    let iban = param.value().as_str().ok_or(
+        RenderErrorReason::InvalidParamType(
+            "iban helper requires a string parameter",
+    ))?;
+
+    out.write(&validation::format_iban(iban))?;
+
+    Ok(())
+}
+
+/// BIC as entered, without spaces and in upper case
+fn bic (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
+    let param = h.param(0).ok_or(
+        RenderErrorReason::ParamNotFoundForIndex(
+            "bic helper requires at least one parameter",
+            0,
+    ))?;
+
+    let bic = param.value().as_str().ok_or(
+        RenderErrorReason::InvalidParamType(
+            "bic helper requires a string parameter",
+    ))?;
+
+    out.write(&validation::normalize(bic))?;
+
+    Ok(())
+}
+
+// quantities are written with a , and followed by their unit when one is given
+//     -> 2,5 heures
+fn quantity (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
+    let param = h.param(0).ok_or(
+        RenderErrorReason::ParamNotFoundForIndex(
+            "quantity helper requires at least one parameter",
+            0,
+    ))?;
+
+    let quantity = param.value().as_f64().ok_or(
+        RenderErrorReason::InvalidParamType(
+            "quantity helper requires a number parameter",
+    ))?;
+
+    let unit = match h.param(1) {
+        Some(param) => serde_json::from_value::<Unit>(param.value().clone()).map_err(|_|
+            RenderErrorReason::InvalidParamType(
+                "quantity helper requires a unit as second parameter",
+        ))?,
+        None => Unit::None,
+    };
+
+    out.write(&unit.with_quantity(quantity))?;
+
+    Ok(())
+}
+
+// dates are serialized as yyyy-mm-dd
+//     -> dd/mm/yyyy
+fn french_date (h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> Result<(), RenderError> {
+    let param = h.param(0).ok_or(
+        RenderErrorReason::ParamNotFoundForIndex(
+            "french_date helper requires at least one parameter",
+            0,
+    ))?;
+
+    let date = param.value().as_str()
+        .and_then(|date| date.parse::<NaiveDate>().ok())
+        .ok_or(
+            RenderErrorReason::InvalidParamType(
+                "french_date helper requires a yyyy-mm-dd date parameter",
+        ))?;
+
+    out.write(&date.format("%d/%m/%Y").to_string())?;
+
+    Ok(())
+}
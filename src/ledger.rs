@@ -0,0 +1,66 @@
+//! livre des recettes: chronological record of the payments received for the archived invoices
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use crate::archive::Entry;
+use crate::latex::InvoiceData;
+use crate::money::Money;
+
+pub mod csv;
+pub mod ods;
+pub mod pdf;
+
+
+/// a payment received, as a line of the livre des recettes
+#[derive(Debug, Clone, Serialize)]
+pub struct Receipt {
+    pub date: NaiveDate,
+    pub number: String,
+    pub client: String,
+    pub nature: String,
+    pub amount: Money,
+    /// label of the payment method
+    pub method: &'static str,
+}
+
+/// columns of the exported ledgers
+const COLUMNS: [&str; 6] = ["Date", "N° de facture", "Client", "Nature", "Montant", "Mode de règlement"];
+
+/// payments received between start and end included, in chronological order
+pub fn receipts(entries: &[Entry], start: NaiveDate, end: NaiveDate) -> Vec<Receipt> {
+    let mut receipts: Vec<Receipt> = entries.iter()
+        .filter(|entry| !entry.invoice.is_devis)
+        .flat_map(|entry| entry.payments.iter()
+            .filter(|payment| (start..=end).contains(&payment.date))
+            .map(|payment| Receipt {
+                date: payment.date,
+                number: entry.invoice.number.clone(),
+                client: entry.invoice.client.name.clone(),
+                nature: nature(&entry.invoice),
+                amount: payment.amount,
+                method: payment.method.label(),
+            }))
+        .collect();
+    receipts.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.number.cmp(&b.number)));
+    receipts
+}
+
+pub fn total(receipts: &[Receipt]) -> Money {
+    receipts.iter().map(|receipt| receipt.amount).sum()
+}
+
+/// nature of the invoice on a single line, the products when it has none
+fn nature(invoice: &InvoiceData) -> String {
+    if invoice.nature.trim().is_empty() {
+        return invoice.products.iter()
+            .map(|product| product.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+    invoice.nature.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+use crate::ledger::{Receipt, COLUMNS};
+
+
+/// semicolon separated with decimal commas, as read by spreadsheets with a french locale
+pub fn write_file(path: &Path, receipts: &[Receipt]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_path(path)?;
+    writer.write_record(COLUMNS)?;
+    for receipt in receipts {
+        writer.write_record([
+            receipt.date.format("%d/%m/%Y").to_string(),
+            receipt.number.clone(),
+            receipt.client.clone(),
+            receipt.nature.clone(),
+            receipt.amount.french(),
+            receipt.method.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
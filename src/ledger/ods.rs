@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::ledger::{total, Receipt, COLUMNS};
+use crate::money::Money;
+
+
+const MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+ <manifest:file-entry manifest:full-path="/" manifest:version="1.2" manifest:media-type="application/vnd.oasis.opendocument.spreadsheet"/>
+ <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+/// minimal OpenDocument spreadsheet, with typed cells and a total formula
+pub fn write_file(path: &Path, receipts: &[Receipt]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut zip = ZipWriter::new(File::create(path)?);
+    // the mimetype comes first and uncompressed, so that the format can be recognized
+    zip.start_file("mimetype", SimpleFileOptions::default().compression_method(CompressionMethod::Stored))?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+    zip.start_file("META-INF/manifest.xml", SimpleFileOptions::default())?;
+    zip.write_all(MANIFEST.as_bytes())?;
+    zip.start_file("content.xml", SimpleFileOptions::default())?;
+    zip.write_all(content(receipts).as_bytes())?;
+    zip.finish()?;
+    Ok(())
+}
+
+fn content(receipts: &[Receipt]) -> String {
+    let mut rows = vec![row(COLUMNS.map(text_cell))];
+    for receipt in receipts {
+        rows.push(row([
+            date_cell(receipt.date),
+            text_cell(&receipt.number),
+            text_cell(&receipt.client),
+            text_cell(&receipt.nature),
+            amount_cell(receipt.amount, None),
+            text_cell(receipt.method),
+        ]));
+    }
+    // amounts are in the fifth column, from the second row
+    let formula = format!("of:=SUM([.E2:.E{}])", receipts.len() + 1);
+    rows.push(row([
+        text_cell("Total"),
+        empty_cell(),
+        empty_cell(),
+        empty_cell(),
+        amount_cell(total(receipts), Some(&formula)),
+        empty_cell(),
+    ]));
+
+    format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2" office:version="1.2">
+ <office:body>
+  <office:spreadsheet>
+   <table:table table:name="Livre des recettes">
+    <table:table-column table:number-columns-repeated="6"/>
+{}
+   </table:table>
+  </office:spreadsheet>
+ </office:body>
+</office:document-content>
+"#, rows.join("\n"))
+}
+
+fn row<const N: usize>(cells: [String; N]) -> String {
+    format!("    <table:table-row>{}</table:table-row>", cells.join(""))
+}
+
+fn text_cell(value: &str) -> String {
+    format!(r#"<table:table-cell office:value-type="string"><text:p>{}</text:p></table:table-cell>"#, escape(value))
+}
+
+fn date_cell(date: NaiveDate) -> String {
+    format!(r#"<table:table-cell office:value-type="date" office:date-value="{}"><text:p>{}</text:p></table:table-cell>"#,
+        date.format("%Y-%m-%d"), date.format("%d/%m/%Y"))
+}
+
+fn amount_cell(amount: Money, formula: Option<&str>) -> String {
+    let formula = formula.map(|formula| format!(r#" table:formula="{formula}""#)).unwrap_or_default();
+    format!(r#"<table:table-cell{formula} office:value-type="currency" office:currency="EUR" office:value="{amount}"><text:p>{} €</text:p></table:table-cell>"#,
+        amount.french())
+}
+
+fn empty_cell() -> String {
+    "<table:table-cell/>".to_string()
+}
+
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use handlebars::to_json;
+use serde::Serialize;
+use serde_json::Map;
+
+use crate::app::Author;
+use crate::latex;
+use crate::money::Money;
+use crate::ledger::{total, Receipt};
+
+
+const TEMPLATE_STR: &str = include_str!("../../assets/ledger.tex");
+
+#[derive(Serialize)]
+struct Ledger<'a> {
+    author: &'a Author,
+    start: NaiveDate,
+    end: NaiveDate,
+    receipts: &'a [Receipt],
+    total: Money,
+}
+
+pub fn write_file(
+    path: &Path,
+    receipts: &[Receipt],
+    author: &Author,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = Map::new();
+    data.insert("ledger".to_string(), to_json(Ledger {
+        author,
+        start,
+        end,
+        receipts,
+        total: total(receipts),
+    }));
+    let content = latex::registry().render_template(TEMPLATE_STR, &data)?;
+    latex::latex_to_pdf(&content, "livre-des-recettes")?
+        .export(&path.to_string_lossy())?;
+    Ok(())
+}
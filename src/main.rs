@@ -3,7 +3,20 @@ use std::sync::{LazyLock, Mutex};
 use relm4::prelude::*;
 
 mod app;
+mod archive;
+mod contacts;
+mod contributions;
+mod declaration;
+mod dispenses;
+mod epc;
+mod fec;
+mod files;
+mod fuzzy;
 mod latex;
+mod ledger;
+mod money;
+mod stats;
+mod validation;
 mod config;
 
 
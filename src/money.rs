@@ -0,0 +1,247 @@
+//! amounts in euros, kept as a whole number of cents so that sums are exact
+//!
+//! serialized as a number of euros, as the amounts stored before
+
+use std::fmt::{self, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+/// decimals kept for the rates of `Money::percent`
+const RATE_SCALE: i128 = 1_000_000;
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Money {
+        Money(cents)
+    }
+
+    /// rounded to the nearest cent
+    pub fn from_euros(euros: f64) -> Money {
+        Money((euros * 100.0).round() as i64)
+    }
+
+    /// for ratios and widgets only, amounts are never computed as floats
+    pub fn euros(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    /// total of a line, the quantity being rounded to two decimals
+    ///     12,50 € x 2,5 -> 31,25 €
+    pub fn times(self, quantity: f64) -> Money {
+        let hundredths = (quantity * 100.0).round() as i128;
+        Money(div_round(self.0 as i128 * hundredths, 100))
+    }
+
+    /// `rate` % of the amount, the rate being rounded to six decimals
+    pub fn percent(self, rate: f64) -> Money {
+        let rate = (rate * RATE_SCALE as f64).round() as i128;
+        Money(div_round(self.0 as i128 * rate, 100 * RATE_SCALE))
+    }
+
+    /// part of the amount in proportion of `part` over `whole`, zero when `whole` is
+    pub fn prorata(self, part: Money, whole: Money) -> Money {
+        if whole.0 == 0 {
+            return Money::ZERO;
+        }
+        let (part, whole) = if whole.0 < 0 { (-part.0, -whole.0) } else { (part.0, whole.0) };
+        Money(div_round(self.0 as i128 * part as i128, whole as i128))
+    }
+
+    /// with a decimal comma, as written in french documents
+    ///     -> 1234,50
+    pub fn french(self) -> String {
+        self.to_string().replace('.', ",")
+    }
+}
+
+/// rounded to the nearest integer, halves away from zero
+fn div_round(numerator: i128, denominator: i128) -> i64 {
+    let quotient = (numerator.abs() + denominator / 2) / denominator;
+    (numerator.signum() * quotient) as i64
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(f, "{sign}{}.{:02}", self.0.abs() / 100, self.0.abs() % 100)
+    }
+}
+
+/// amounts entered by the user, with a decimal point or comma and at most two decimals
+impl FromStr for Money {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().replace(',', ".");
+        let (negative, value) = match value.strip_prefix('-') {
+            Some(value) => (true, value),
+            None => (false, value.as_str()),
+        };
+        let (euros, decimals) = value.split_once('.').unwrap_or((value, ""));
+        if euros.is_empty() && decimals.is_empty() {
+            return Err("Montant vide".to_string());
+        }
+        if !euros.chars().chain(decimals.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(format!("Montant invalide : {value}"));
+        }
+        if decimals.len() > 2 {
+            return Err("Au plus deux décimales".to_string());
+        }
+        let euros: i64 = if euros.is_empty() { 0 } else {
+            euros.parse().map_err(|_| format!("Montant trop grand : {value}"))?
+        };
+        let cents: i64 = format!("{decimals:0<2}").parse().unwrap();
+        let cents = euros.checked_mul(100)
+            .and_then(|euros| euros.checked_add(cents))
+            .ok_or(format!("Montant trop grand : {value}"))?;
+        Ok(Money(if negative { -cents } else { cents }))
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.euros())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from_euros)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!("12".parse(), Ok(Money(1200)));
+        assert_eq!("12,5".parse(), Ok(Money(1250)));
+        assert_eq!(" 12.05 ".parse(), Ok(Money(1205)));
+        assert_eq!(",5".parse(), Ok(Money(50)));
+        assert_eq!("-3,20".parse(), Ok(Money(-320)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<Money>(), Err("Montant vide".to_string()));
+        assert_eq!(" , ".parse::<Money>(), Err("Montant vide".to_string()));
+        assert_eq!("12,345".parse::<Money>(), Err("Au plus deux décimales".to_string()));
+        assert_eq!("12 €".parse::<Money>(), Err("Montant invalide : 12 €".to_string()));
+        assert_eq!("1,2,3".parse::<Money>(), Err("Montant invalide : 1.2.3".to_string()));
+        assert_eq!("--1".parse::<Money>(), Err("Montant invalide : -1".to_string()));
+        assert_eq!("99999999999999999999".parse::<Money>(), Err("Montant trop grand : 99999999999999999999".to_string()));
+        // fits in an i64 as euros, but not as cents
+        assert_eq!("92233720368547759".parse::<Money>(), Err("Montant trop grand : 92233720368547759".to_string()));
+        assert_eq!("92233720368547758,07".parse(), Ok(Money(i64::MAX)));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Money(123450).to_string(), "1234.50");
+        assert_eq!(Money(-5).to_string(), "-0.05");
+        assert_eq!(Money(-123450).french(), "-1234,50");
+    }
+
+    #[test]
+    fn times_rounds_halves_away_from_zero() {
+        assert_eq!(Money(1250).times(2.5), Money(3125));
+        assert_eq!(Money(1).times(0.5), Money(1));
+        assert_eq!(Money(-1).times(0.5), Money(-1));
+        assert_eq!(Money(333).times(1.5), Money(500));
+        assert_eq!(Money(-333).times(1.5), Money(-500));
+        // the quantity is rounded to hundredths first
+        assert_eq!(Money(10000).times(0.333), Money(3300));
+    }
+
+    #[test]
+    fn percent_rounds_halves_away_from_zero() {
+        assert_eq!(Money(10000).percent(6.9), Money(690));
+        assert_eq!(Money(50).percent(1.0), Money(1));
+        assert_eq!(Money(-50).percent(1.0), Money(-1));
+        assert_eq!(Money(49).percent(1.0), Money(0));
+        assert_eq!(Money(12345).percent(9.2), Money(1136));
+        assert_eq!(Money(-12345).percent(9.2), Money(-1136));
+    }
+
+    #[test]
+    fn prorata() {
+        assert_eq!(Money(1000).prorata(Money(1), Money(3)), Money(333));
+        assert_eq!(Money(1000).prorata(Money(2), Money(3)), Money(667));
+        assert_eq!(Money(1000).prorata(Money(5), Money(0)), Money::ZERO);
+        assert_eq!(Money(1000).prorata(Money(-1), Money(-4)), Money(250));
+        assert_eq!(Money(1000).prorata(Money(1), Money(-4)), Money(-250));
+    }
+
+    #[test]
+    fn serde_as_euros() {
+        assert_eq!(serde_json::to_string(&Money(1250)).unwrap(), "12.5");
+        assert_eq!(serde_json::from_str::<Money>("12.5").unwrap(), Money(1250));
+        // amounts archived as floats, before they were kept in cents
+        assert_eq!(serde_json::from_str::<Money>("0.30000000000000004").unwrap(), Money(30));
+        assert_eq!(serde_json::from_str::<Money>("1234.4999999999998").unwrap(), Money(123450));
+        assert_eq!(serde_json::from_str::<Money>("-7.1").unwrap(), Money(-710));
+        assert_eq!(serde_json::from_str::<Money>("20").unwrap(), Money(2000));
+        for cents in [0, 1, 10, 99, 12345, -12345, 10_000_000_001] {
+            let json = serde_json::to_string(&Money(cents)).unwrap();
+            assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), Money(cents));
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! activity statistics computed from the invoice archive
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::app::ClientId;
+use crate::archive::Entry;
+use crate::money::Money;
+
+
+/// revenue ceilings of a given year, in €
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    /// first year these thresholds apply to, until the next entry
+    pub year: i32,
+    /// micro-entreprise regime for services (BNC)
+    pub micro_entreprise: Money,
+    /// VAT franchise en base for services
+    pub vat_franchise: Money,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            year: 2023,
+            micro_entreprise: Money::from_cents(7_770_000),
+            vat_franchise: Money::from_cents(3_680_000),
+        }
+    }
+}
+
+impl Thresholds {
+    /// thresholds of the most recent entry starting before or during `year`
+    pub fn for_year(thresholds: &[Thresholds], year: i32) -> Thresholds {
+        thresholds.iter()
+            .filter(|thresholds| thresholds.year <= year)
+            .max_by_key(|thresholds| thresholds.year)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Month,
+    Quarter,
+    Year,
+}
+
+impl Period {
+    /// in the same order as the period dropdown
+    pub const ALL: [Period; 3] = [Period::Month, Period::Quarter, Period::Year];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Period::Month => "Mois",
+            Period::Quarter => "Trimestre",
+            Period::Year => "Année",
+        }
+    }
+
+    /// periods of the year, named, with their first and last days
+    pub fn periods(&self, year: i32) -> Vec<(String, NaiveDate, NaiveDate)> {
+        let (count, months) = match self {
+            Period::Month => (12, 1),
+            Period::Quarter => (4, 3),
+            Period::Year => (1, 12),
+        };
+        (1..=count)
+            .map(|index| {
+                let first_month = (index - 1) * months + 1;
+                let start = NaiveDate::from_ymd_opt(year, first_month, 1).unwrap();
+                let end = start
+                    .checked_add_months(chrono::Months::new(months))
+                    .and_then(|next| next.pred_opt())
+                    .unwrap();
+                let key = if *self == Period::Year { 0 } else { index };
+                (self.name((year, key)), start, end)
+            })
+            .collect()
+    }
+
+    /// year and index of the period containing the date
+    fn key(&self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            Period::Month => (date.year(), date.month()),
+            Period::Quarter => (date.year(), (date.month() - 1) / 3 + 1),
+            Period::Year => (date.year(), 0),
+        }
+    }
+
+    fn name(&self, (year, index): (i32, u32)) -> String {
+        const MONTHS: [&str; 12] = [
+            "Janvier", "Février", "Mars", "Avril", "Mai", "Juin",
+            "Juillet", "Août", "Septembre", "Octobre", "Novembre", "Décembre",
+        ];
+        match self {
+            Period::Month => format!("{} {year}", MONTHS[index as usize - 1]),
+            Period::Quarter => format!("T{index} {year}"),
+            Period::Year => year.to_string(),
+        }
+    }
+}
+
+/// revenue invoiced, by invoice date, and collected, by payment date
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Revenue {
+    pub label: String,
+    pub invoiced: Money,
+    pub collected: Money,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuoteRate {
+    pub issued: usize,
+    /// followed by an invoice
+    pub accepted: usize,
+}
+
+impl QuoteRate {
+    /// in %, none without quotes
+    pub fn percent(&self) -> Option<f64> {
+        (self.issued > 0).then(|| 100.0 * self.accepted as f64 / self.issued as f64)
+    }
+}
+
+/// revenue of each period, most recent first
+///
+/// months and quarters are those of `year`, years are all the archived ones
+pub fn by_period(entries: &[Entry], period: Period, year: i32) -> Vec<Revenue> {
+    let mut revenues: HashMap<(i32, u32), Revenue> = HashMap::new();
+    if period != Period::Year {
+        let periods = if period == Period::Month { 12 } else { 4 };
+        for index in 1..=periods {
+            revenues.insert((year, index), Revenue::default());
+        }
+    }
+
+    for entry in invoices(entries) {
+        let key = period.key(entry.invoice.date);
+        if period == Period::Year || key.0 == year {
+            revenues.entry(key).or_default().invoiced += gross(entry);
+        }
+        for payment in &entry.payments {
+            let key = period.key(payment.date);
+            if period == Period::Year || key.0 == year {
+                revenues.entry(key).or_default().collected += payment.amount;
+            }
+        }
+    }
+
+    let mut keys: Vec<(i32, u32)> = revenues.keys().copied().collect();
+    keys.sort_by(|a, b| b.cmp(a));
+    keys.into_iter()
+        .map(|key| Revenue { label: period.name(key), ..revenues[&key].clone() })
+        .collect()
+}
+
+/// revenue of the year by client, largest first
+pub fn by_client(entries: &[Entry], year: i32) -> Vec<Revenue> {
+    let mut revenues: HashMap<ClientId, Revenue> = HashMap::new();
+    for entry in invoices(entries) {
+        let revenue = revenues.entry(entry.client_id()).or_default();
+        // entries are sorted by date, the latest name the client was invoiced with is kept
+        revenue.label = entry.invoice.client.name.clone();
+        if entry.invoice.date.year() == year {
+            revenue.invoiced += gross(entry);
+        }
+        revenue.collected += entry.payments.iter()
+            .filter(|payment| payment.date.year() == year)
+            .map(|payment| payment.amount)
+            .sum::<Money>();
+    }
+
+    let mut revenues: Vec<Revenue> = revenues.values()
+        .filter(|revenue| revenue.invoiced != Money::ZERO || revenue.collected != Money::ZERO)
+        .cloned()
+        .collect();
+    revenues.sort_by(|a, b| b.invoiced.cmp(&a.invoiced).then_with(|| a.label.cmp(&b.label)));
+    revenues
+}
+
+/// quotes of the year, accepted when an invoice was made from them
+///
+/// invoices that don't record their quote, as those archived before it was recorded,
+/// accept a quote of the same client and total issued before them, a single one each
+pub fn quote_rate(entries: &[Entry], year: i32) -> QuoteRate {
+    let accepted: HashSet<&str> = invoices(entries)
+        .filter_map(|entry| entry.invoice.quote.as_deref())
+        .collect();
+    let mut unlinked: Vec<&Entry> = invoices(entries)
+        .filter(|entry| entry.invoice.quote.is_none())
+        .collect();
+    let mut rate = QuoteRate::default();
+    for quote in entries.iter().filter(|entry| entry.invoice.is_devis && entry.invoice.date.year() == year) {
+        rate.issued += 1;
+        if accepted.contains(quote.invoice.number.as_str()) {
+            rate.accepted += 1;
+            continue;
+        }
+        let accepting = unlinked.iter().position(|invoice| {
+            invoice.client_id() == quote.client_id()
+                && invoice.invoice.date >= quote.invoice.date
+                && gross(invoice) == gross(quote)
+        });
+        if let Some(index) = accepting {
+            unlinked.remove(index);
+            rate.accepted += 1;
+        }
+    }
+    rate
+}
+
+/// revenue invoiced and collected during the year
+pub fn of_year(entries: &[Entry], year: i32) -> Revenue {
+    Revenue {
+        label: year.to_string(),
+        invoiced: invoices(entries)
+            .filter(|entry| entry.invoice.date.year() == year)
+            .map(gross)
+            .sum(),
+        collected: collected(entries, year),
+    }
+}
+
+/// revenue collected during the year, which the thresholds apply to
+pub fn collected(entries: &[Entry], year: i32) -> Money {
+    invoices(entries)
+        .flat_map(|entry| &entry.payments)
+        .filter(|payment| payment.date.year() == year)
+        .map(|payment| payment.amount)
+        .sum()
+}
+
+fn invoices(entries: &[Entry]) -> impl Iterator<Item = &Entry> {
+    entries.iter().filter(|entry| !entry.invoice.is_devis)
+}
+
+/// invoiced amount, before the contributions withheld by the diffuseur
+fn gross(entry: &Entry) -> Money {
+    entry.invoice.total()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{Client, Product};
+    use crate::archive::{Payment, PaymentMethod};
+    use crate::latex::InvoiceData;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn client(name: &str) -> Client {
+        Client { name: name.to_string(), ..Client::new() }
+    }
+
+    fn entry(client: &Client, number: &str, date: NaiveDate, cents: i64) -> Entry {
+        Entry {
+            invoice: InvoiceData {
+                number: number.to_string(),
+                client: client.clone(),
+                products: vec![Product { total: Money::from_cents(cents), ..Product::default() }],
+                date,
+                ..InvoiceData::default()
+            },
+            payments: Vec::new(),
+        }
+    }
+
+    fn quote(client: &Client, number: &str, date: NaiveDate, cents: i64) -> Entry {
+        let mut quote = entry(client, number, date, cents);
+        quote.invoice.is_devis = true;
+        quote
+    }
+
+    fn paid(mut entry: Entry, date: NaiveDate, cents: i64) -> Entry {
+        entry.payments.push(Payment { date, amount: Money::from_cents(cents), method: PaymentMethod::Transfer });
+        entry
+    }
+
+    #[test]
+    fn thresholds_of_year() {
+        let thresholds = [
+            Thresholds::default(),
+            Thresholds { year: 2026, micro_entreprise: Money::from_cents(8_350_000), ..Thresholds::default() },
+        ];
+        assert_eq!(Thresholds::for_year(&thresholds, 2025), Thresholds::default());
+        assert_eq!(Thresholds::for_year(&thresholds, 2026).micro_entreprise, Money::from_cents(8_350_000));
+        assert_eq!(Thresholds::for_year(&thresholds, 2030).micro_entreprise, Money::from_cents(8_350_000));
+        // before the first entry
+        assert_eq!(Thresholds::for_year(&thresholds[1..], 2024), Thresholds::default());
+    }
+
+    #[test]
+    fn periods_of_year() {
+        let quarters = Period::Quarter.periods(2024);
+        assert_eq!(quarters.len(), 4);
+        assert_eq!(quarters[0], ("T1 2024".to_string(), date(2024, 1, 1), date(2024, 3, 31)));
+        assert_eq!(quarters[3], ("T4 2024".to_string(), date(2024, 10, 1), date(2024, 12, 31)));
+
+        let months = Period::Month.periods(2024);
+        assert_eq!(months[1], ("Février 2024".to_string(), date(2024, 2, 1), date(2024, 2, 29)));
+        assert_eq!(Period::Year.periods(2024), [("2024".to_string(), date(2024, 1, 1), date(2024, 12, 31))]);
+    }
+
+    #[test]
+    fn revenue_by_quarter() {
+        let client = client("Dupont");
+        let entries = [
+            paid(entry(&client, "F-001", date(2023, 12, 31), 10000), date(2024, 1, 1), 10000),
+            paid(entry(&client, "F-002", date(2024, 3, 31), 20000), date(2024, 4, 1), 20000),
+            quote(&client, "D-001", date(2024, 5, 1), 40000),
+        ];
+        let revenues = by_period(&entries, Period::Quarter, 2024);
+
+        // most recent first, empty quarters included
+        let labels: Vec<&str> = revenues.iter().map(|revenue| revenue.label.as_str()).collect();
+        assert_eq!(labels, ["T4 2024", "T3 2024", "T2 2024", "T1 2024"]);
+        assert_eq!((revenues[3].invoiced, revenues[3].collected), (Money::from_cents(20000), Money::from_cents(10000)));
+        assert_eq!((revenues[2].invoiced, revenues[2].collected), (Money::ZERO, Money::from_cents(20000)));
+        assert_eq!(revenues[0], Revenue { label: "T4 2024".to_string(), ..Revenue::default() });
+
+        let years = by_period(&entries, Period::Year, 2024);
+        assert_eq!(years[0], Revenue { label: "2024".to_string(), invoiced: Money::from_cents(20000), collected: Money::from_cents(30000) });
+        assert_eq!(years[1], Revenue { label: "2023".to_string(), invoiced: Money::from_cents(10000), collected: Money::ZERO });
+    }
+
+    #[test]
+    fn collected_by_payment_date() {
+        let client = client("Dupont");
+        let entries = [
+            paid(paid(entry(&client, "F-001", date(2024, 12, 1), 30000), date(2024, 12, 31), 10000), date(2025, 1, 1), 20000),
+            paid(entry(&client, "F-002", date(2025, 6, 1), 50000), date(2025, 6, 2), 50000),
+        ];
+        assert_eq!(collected(&entries, 2024), Money::from_cents(10000));
+        assert_eq!(collected(&entries, 2025), Money::from_cents(70000));
+        assert_eq!(collected(&entries, 2026), Money::ZERO);
+    }
+
+    #[test]
+    fn quotes_accepted_by_their_invoice() {
+        let client = client("Dupont");
+        let mut invoice = entry(&client, "F-001", date(2025, 2, 1), 20000);
+        invoice.invoice.quote = Some("D-001".to_string());
+        let entries = [
+            quote(&client, "D-001", date(2025, 1, 1), 10000),
+            quote(&client, "D-002", date(2025, 1, 1), 10000),
+            invoice,
+        ];
+        // the linked invoice accepts its quote even with another total, and no other
+        assert_eq!(quote_rate(&entries, 2025), QuoteRate { issued: 2, accepted: 1 });
+        assert_eq!(quote_rate(&entries, 2025).percent(), Some(50.0));
+        assert_eq!(quote_rate(&entries, 2024).percent(), None);
+    }
+
+    #[test]
+    fn quotes_accepted_by_unlinked_invoices() {
+        let dupont = client("Dupont");
+        let durand = client("Durand");
+        let entries = [
+            quote(&dupont, "D-001", date(2025, 1, 1), 10000),
+            quote(&dupont, "D-002", date(2025, 1, 2), 10000),
+            quote(&dupont, "D-003", date(2025, 3, 1), 10000),
+            quote(&durand, "D-004", date(2025, 1, 1), 10000),
+            // same client and total, issued after the quote, accepts a single one
+            entry(&dupont, "F-001", date(2025, 2, 1), 10000),
+            // other total
+            entry(&dupont, "F-002", date(2025, 2, 1), 15000),
+        ];
+        assert_eq!(quote_rate(&entries, 2025), QuoteRate { issued: 4, accepted: 1 });
+    }
+}
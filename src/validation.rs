@@ -0,0 +1,302 @@
+//! checks of french company identifiers, european VAT numbers and bank details,
+//! errors are messages displayed next to the form fields
+
+/// SIREN of La Poste, whose establishments don't follow the Luhn rule
+const LA_POSTE_SIREN: &str = "356000000";
+
+/// digits of an identifier typed with spaces, dots or dashes
+pub fn normalize(value: &str) -> String {
+    value.chars()
+        .filter(|c| !c.is_whitespace() && *c != '.' && *c != '-')
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
+pub fn siren(value: &str) -> Result<(), String> {
+    let siren = normalize(value);
+    if siren.len() != 9 || !siren.chars().all(|c| c.is_ascii_digit()) {
+        return Err("9 chiffres attendus".to_string());
+    }
+    if !luhn(&siren) {
+        return Err("clé de contrôle invalide".to_string());
+    }
+    Ok(())
+}
+
+pub fn siret(value: &str) -> Result<(), String> {
+    let siret = normalize(value);
+    if siret.len() != 14 || !siret.chars().all(|c| c.is_ascii_digit()) {
+        return Err("14 chiffres attendus".to_string());
+    }
+    siren(&siret[..9]).map_err(|_| "SIREN invalide".to_string())?;
+    // establishments of La Poste have a digit sum multiple of 5 instead
+    let la_poste = siret.starts_with(LA_POSTE_SIREN) && digit_sum(&siret) % 5 == 0;
+    if !luhn(&siret) && !la_poste {
+        return Err("clé de contrôle invalide".to_string());
+    }
+    Ok(())
+}
+
+/// APE code of the NAF rév. 2 nomenclature, e.g. 90.03A
+pub fn ape(value: &str) -> Result<(), String> {
+    let ape: Vec<char> = value.trim().to_uppercase().chars().filter(|c| *c != '.').collect();
+    let valid = ape.len() == 5
+        && ape[..4].iter().all(|c| c.is_ascii_digit())
+        && ape[4].is_ascii_uppercase();
+    if !valid {
+        return Err("format attendu : 90.03A".to_string());
+    }
+    Ok(())
+}
+
+/// french VAT key of a SIREN
+pub fn french_vat_key(siren: &str) -> Option<u64> {
+    let siren: u64 = normalize(siren).parse().ok()?;
+    Some((12 + 3 * (siren % 97)) % 97)
+}
+
+/// european VAT number, checked against the SIRET of the company when french
+pub fn vat(value: &str, siret: Option<&str>) -> Result<(), String> {
+    let vat = normalize(value);
+    let (prefix, number) = vat.split_at(vat.char_indices().nth(2).map_or(vat.len(), |(i, _)| i));
+    let formats = vat_formats(prefix).ok_or_else(|| format!("préfixe pays {prefix} inconnu"))?;
+    if !formats.iter().any(|format| matches_format(number, format)) {
+        return Err(format!("format attendu : {prefix}{}", formats[0]));
+    }
+
+    if prefix == "FR" {
+        let (key, siren) = number.split_at(2);
+        self::siren(siren).map_err(|_| "SIREN invalide".to_string())?;
+        // keys of newer numbers contain letters and can't be computed
+        if let Ok(key) = key.parse::<u64>() {
+            if Some(key) != french_vat_key(siren) {
+                return Err(format!("clé invalide, {:02} attendu", french_vat_key(siren).unwrap_or_default()));
+            }
+        }
+        // the SIRET field may hold anything while it's being typed
+        if let Some(siret) = siret.map(normalize) {
+            if siret.get(..9).is_some_and(|siret| siret != siren) {
+                return Err("ne correspond pas au SIRET".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// international bank account number, ISO 13616
+pub fn iban(value: &str) -> Result<(), String> {
+    let iban = normalize(value);
+    if iban.len() < 5 || !iban.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err("caractères invalides".to_string());
+    }
+    if let Some(length) = iban_length(&iban[..2]) {
+        if iban.len() != length {
+            return Err(format!("{length} caractères attendus"));
+        }
+    }
+    // country code and check digits are moved to the end, letters count as 10 to 35
+    let remainder = iban[4..].chars().chain(iban[..4].chars())
+        .fold(0, |remainder, c| {
+            let value = c.to_digit(36).unwrap_or_default();
+            let shift = if value < 10 { 10 } else { 100 };
+            (remainder * shift + value) % 97
+        });
+    if remainder != 1 {
+        return Err("clé de contrôle invalide".to_string());
+    }
+    Ok(())
+}
+
+/// IBAN grouped by blocks of four characters
+pub fn format_iban(value: &str) -> String {
+    normalize(value)
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(4)
+        .map(|block| block.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// bank identifier code, ISO 9362: bank, country, location and optional branch
+pub fn bic(value: &str) -> Result<(), String> {
+    let bic: Vec<char> = normalize(value).chars().collect();
+    let valid = (bic.len() == 8 || bic.len() == 11)
+        && bic[..6].iter().all(|c| c.is_ascii_uppercase())
+        && bic[6..].iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if !valid {
+        return Err("format attendu : BNPAFRPPXXX".to_string());
+    }
+    Ok(())
+}
+
+/// length of the IBANs of the SEPA countries
+fn iban_length(country: &str) -> Option<usize> {
+    Some(match country {
+        "NO" => 15,
+        "BE" => 16,
+        "DK" | "FI" | "NL" => 18,
+        "SI" => 19,
+        "AT" | "EE" | "LT" | "LU" => 20,
+        "CH" | "HR" | "LI" | "LV" => 21,
+        "BG" | "DE" | "GB" | "IE" | "VA" => 22,
+        "GI" => 23,
+        "AD" | "CZ" | "ES" | "RO" | "SE" | "SK" => 24,
+        "PT" => 25,
+        "IS" => 26,
+        "FR" | "GR" | "IT" | "MC" | "SM" => 27,
+        "CY" | "HU" | "PL" => 28,
+        "MT" => 31,
+        _ => return None,
+    })
+}
+
+/// formats of the VAT numbers of each member state, without the country prefix:
+/// `9` stands for a digit, `A` for a letter, `X` for either
+fn vat_formats(prefix: &str) -> Option<&'static [&'static str]> {
+    Some(match prefix {
+        "AT" => &["U99999999"],
+        "BE" => &["0999999999", "1999999999"],
+        "BG" => &["999999999", "9999999999"],
+        "CY" => &["99999999A"],
+        "CZ" => &["99999999", "999999999", "9999999999"],
+        "DE" => &["999999999"],
+        "DK" => &["99999999"],
+        "EE" => &["999999999"],
+        "EL" => &["999999999"],
+        "ES" => &["X9999999X"],
+        "FI" => &["99999999"],
+        "FR" => &["XX999999999"],
+        "HR" => &["99999999999"],
+        "HU" => &["99999999"],
+        "IE" => &["9999999A", "9999999AA", "9X99999A"],
+        "IT" => &["99999999999"],
+        "LT" => &["999999999", "999999999999"],
+        "LU" => &["99999999"],
+        "LV" => &["99999999999"],
+        "MT" => &["99999999"],
+        "NL" => &["999999999B99"],
+        "PL" => &["9999999999"],
+        "PT" => &["999999999"],
+        "RO" => &["99", "999", "9999", "99999", "999999", "9999999", "99999999", "999999999", "9999999999"],
+        "SE" => &["999999999999"],
+        "SI" => &["99999999"],
+        "SK" => &["9999999999"],
+        "XI" => &["999999999", "999999999999", "GD999", "HA999"],
+        _ => return None,
+    })
+}
+
+fn matches_format(number: &str, format: &str) -> bool {
+    number.chars().count() == format.len()
+        && number.chars().zip(format.chars()).all(|(c, f)| match f {
+            '9' => c.is_ascii_digit(),
+            'A' => c.is_ascii_uppercase(),
+            'X' => c.is_ascii_digit() || c.is_ascii_uppercase(),
+            f => c == f,
+        })
+}
+
+fn luhn(digits: &str) -> bool {
+    let sum: u32 = digits.chars().rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, digit)| if i % 2 == 1 { let d = digit * 2; if d > 9 { d - 9 } else { d } } else { digit })
+        .sum();
+    sum % 10 == 0
+}
+
+fn digit_sum(digits: &str) -> u32 {
+    digits.chars().filter_map(|c| c.to_digit(10)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn siren_luhn() {
+        assert_eq!(siren("732 829 320"), Ok(()));
+        assert_eq!(siren("443061841"), Ok(()));
+        assert_eq!(siren("732829321"), Err("clé de contrôle invalide".to_string()));
+        assert_eq!(siren("73282932"), Err("9 chiffres attendus".to_string()));
+        assert_eq!(siren("73282932A"), Err("9 chiffres attendus".to_string()));
+    }
+
+    #[test]
+    fn siret_luhn() {
+        assert_eq!(siret("732 829 320 00074"), Ok(()));
+        assert_eq!(siret("73282932000075"), Err("clé de contrôle invalide".to_string()));
+        assert_eq!(siret("73282932100074"), Err("SIREN invalide".to_string()));
+        assert_eq!(siret("7328293200007"), Err("14 chiffres attendus".to_string()));
+    }
+
+    #[test]
+    fn siret_la_poste() {
+        // digit sum multiple of 5, not a valid Luhn number
+        assert!(!luhn("35600000049837"));
+        assert_eq!(siret("356 000 000 49837"), Ok(()));
+        assert!(!luhn("35600000000001"));
+        assert_eq!(siret("35600000000001"), Ok(()));
+        assert_eq!(siret("35600000012345"), Err("clé de contrôle invalide".to_string()));
+        // the exception only applies to La Poste
+        assert!(!luhn("73282932000001"));
+        assert_eq!(siret("73282932000001"), Err("clé de contrôle invalide".to_string()));
+    }
+
+    #[test]
+    fn french_vat() {
+        assert_eq!(french_vat_key("404 833 048"), Some(83));
+        assert_eq!(french_vat_key("443061841"), Some(64));
+        assert_eq!(vat("FR 83 404833048", None), Ok(()));
+        assert_eq!(vat("FR84404833048", None), Err("clé invalide, 83 attendu".to_string()));
+        assert_eq!(vat("FR03404833048", None), Err("clé invalide, 83 attendu".to_string()));
+        // keys with letters can't be checked
+        assert_eq!(vat("FRAB404833048", None), Ok(()));
+        assert_eq!(vat("FR83404833048", Some("404 833 048 00022")), Ok(()));
+        assert_eq!(vat("FR83404833048", Some("44306184100047")), Err("ne correspond pas au SIRET".to_string()));
+        assert_eq!(vat("FR8340483304", None), Err("format attendu : FRXX999999999".to_string()));
+        // not checked against a SIRET being typed, whatever its characters
+        assert_eq!(vat("FR83404833048", Some("4048")), Ok(()));
+        assert_eq!(vat("FR83404833048", Some("éééééé")), Ok(()));
+        assert_eq!(vat("FR83404833048", Some("40483304é")), Ok(()));
+        assert_eq!(vat("FR83404833048", Some("éé4048330480")), Err("ne correspond pas au SIRET".to_string()));
+        assert!(vat("FRéé404833048", None).is_err());
+    }
+
+    #[test]
+    fn european_vat() {
+        assert_eq!(vat("DE123456789", None), Ok(()));
+        assert_eq!(vat("NL123456789B01", None), Ok(()));
+        assert_eq!(vat("DE12345678", None), Err("format attendu : DE999999999".to_string()));
+        assert_eq!(vat("US123456789", None), Err("préfixe pays US inconnu".to_string()));
+        assert!(vat("", None).is_err());
+    }
+
+    #[test]
+    fn iban_mod_97() {
+        assert_eq!(iban("FR76 3000 6000 0112 3456 7890 189"), Ok(()));
+        assert_eq!(iban("de89370400440532013000"), Ok(()));
+        assert_eq!(iban("GB82-WEST-1234-5698-7654-32"), Ok(()));
+        assert_eq!(iban("FR7630006000011234567890188"), Err("clé de contrôle invalide".to_string()));
+        assert_eq!(iban("FR7730006000011234567890189"), Err("clé de contrôle invalide".to_string()));
+        assert_eq!(iban("FR763000600001123456789018"), Err("27 caractères attendus".to_string()));
+        assert_eq!(iban("FR76*3000600001123456789018"), Err("caractères invalides".to_string()));
+        assert_eq!(iban("FR7"), Err("caractères invalides".to_string()));
+    }
+
+    #[test]
+    fn iban_blocks() {
+        assert_eq!(format_iban("fr7630006000011234567890189"), "FR76 3000 6000 0112 3456 7890 189");
+    }
+
+    #[test]
+    fn bic_format() {
+        assert_eq!(bic("BNPAFRPPXXX"), Ok(()));
+        assert_eq!(bic("deut de ff"), Ok(()));
+        assert_eq!(bic("BNPAFRP"), Err("format attendu : BNPAFRPPXXX".to_string()));
+        assert_eq!(bic("BNPAFRPPXX"), Err("format attendu : BNPAFRPPXXX".to_string()));
+        assert_eq!(bic("BNP1FRPPXXX"), Err("format attendu : BNPAFRPPXXX".to_string()));
+        assert_eq!(bic("BNPAFRP_XXX"), Err("format attendu : BNPAFRPPXXX".to_string()));
+    }
+}